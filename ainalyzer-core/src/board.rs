@@ -0,0 +1,693 @@
+use crate::Player;
+use crate::history::{History, Subtree};
+use crate::markup::Markup;
+use sgf_parser::SgfToken;
+use crate::chains::Chains;
+use crate::rules::KoRule;
+use crate::zobrist;
+use petgraph::graph::NodeIndex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+// the engine's vertex, its rows are counted from our first one
+impl std::fmt::Display for Point {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}{}", Point::column_label(self.x), self.y+1)
+    }
+}
+
+impl Point {
+    pub fn new(x: u32, y: u32) -> Self {
+        Self {
+            x,
+            y,
+        }
+    }
+
+    // gtp column letters skip the I
+    pub fn column_label(x: u32) -> char {
+        let x = if x >= 8 {
+            x + 1
+        } else {
+            x
+        };
+        (x as u8 + b'A') as char
+    }
+
+    // the row as read on a board printed with the sgf's top row first, 1 being the bottom one
+    pub fn row_label(y: u32, board_size: u32) -> u32 {
+        board_size - y
+    }
+
+    // the name players use for the point, like Q16
+    pub fn label(&self, board_size: u32) -> String {
+        format!("{}{}", Point::column_label(self.x), Point::row_label(self.y, board_size))
+    }
+
+    pub fn as_coord_tuple(&self) -> (u8, u8) {
+        (self.x as u8, self.y as u8)
+    }
+
+    // sgf coordinates are two letters starting from 'a' in the top left corner
+    pub fn to_sgf(&self) -> String {
+        format!("{}{}", (b'a' + self.x as u8) as char, (b'a' + self.y as u8) as char)
+    }
+
+    pub fn from_sgf(s: &str) -> Option<Self> {
+        match s.as_bytes() {
+            [x @ b'a'..=b'z', y @ b'a'..=b'z'] => Some(Point::new((x - b'a') as u32, (y - b'a') as u32)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stone {
+    pub visible: bool,
+    pub color: Player,
+}
+
+impl Default for Stone {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            color: Player::Black,
+        }
+    }
+}
+
+impl Stone {
+    pub fn new(color: Player) -> Self {
+        Self {
+            visible: true,
+            color
+        }
+    }
+    pub fn black() -> Self {
+        Self {
+            visible: true,
+            color: Player::Black,
+        }
+    }
+
+    pub fn white() -> Self {
+        Self {
+            visible: true,
+            color: Player::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub stones: Vec<Point>,
+    pub liberties: u64,
+    pub team: Player,
+}
+
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub stones: Vec<Stone>, //The points coord are used as the goban coords for the stones to be placed
+    pub last_move: Option<Point>,
+    pub ko: Option<Point>,
+    pub current_move_number: u16,
+    pub total_move_number: u16,
+    pub history: History,
+    pub turn: Player,
+    pub board_size: u32,
+    pub hash: u64, // zobrist hash of the current position
+    pub chains: Chains,
+    pub black_prisoners: u32, // white stones captured by black
+    pub white_prisoners: u32, // black stones captured by white
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(19)
+    }
+}
+
+impl Board {
+    pub fn new(board_size: u32) -> Self {
+        Self {
+            stones: vec![Stone::default(); (board_size*board_size) as usize],
+            last_move: None,
+            ko: None,
+            current_move_number: 0,
+            total_move_number: 0,
+            history: History::new(board_size),
+            turn: Player::Black,
+            board_size,
+            hash: 0,
+            chains: Chains::new(board_size),
+            black_prisoners: 0,
+            white_prisoners: 0,
+        }
+    }
+}
+
+impl Board {
+    pub fn idx_to_coord(i: usize, board_size: u32) -> Point {
+        Point::new(i as u32 / board_size, i as u32 % board_size)
+    }
+
+    pub fn coord_to_idx(p: Point, board_size: u32) -> usize {
+        p.x as usize * board_size as usize + p.y as usize
+    }
+
+    // Traditional hoshi: 4-4 points from 13x13, 3-3 points below, side points from 15x15
+    pub fn star_points(board_size: u32) -> Vec<Point> {
+        let edge = match board_size {
+            0..=6 => return Vec::new(),
+            7..=12 => 2,
+            _ => 3,
+        };
+        let far = board_size - 1 - edge;
+        let mut points = vec![
+            Point::new(edge, edge),
+            Point::new(far, edge),
+            Point::new(edge, far),
+            Point::new(far, far),
+        ];
+        if board_size % 2 == 1 {
+            let middle = board_size / 2;
+            points.push(Point::new(middle, middle));
+            if board_size >= 15 {
+                points.push(Point::new(middle, edge));
+                points.push(Point::new(edge, middle));
+                points.push(Point::new(far, middle));
+                points.push(Point::new(middle, far));
+            }
+        }
+        points
+    }
+
+    // checks the basic rules then looks for a repetition with the history's ko rule
+    pub fn is_legal(&self, p: Point) -> bool {
+        let point = Board::coord_to_idx(p, self.board_size);
+        if self.stones[point].visible || self.ko == Some(p) {
+            return false
+        }
+        let mut has_liberty = self.chains.neighbours(point).any(|n| !self.stones[n].visible);
+        let mut captured = Vec::new();
+        for id in self.chains.adjacent_chains(point) {
+            let chain = self.chains.chain(id);
+            if chain.color == self.turn {
+                // we connect to a friend that still has a liberty elsewhere
+                has_liberty |= chain.liberties.len() > 1;
+            } else if chain.liberties.len() == 1 {
+                captured.push(chain);
+            }
+        }
+        // without captures or liberties the move is a suicide, only allowed if it takes friends along
+        let suicide = if !has_liberty && captured.is_empty() {
+            let friends = self.chains.adjacent_chains(point).into_iter()
+                .filter(|id| self.chains.chain(*id).color == self.turn)
+                .collect::<Vec<_>>();
+            if !self.history.rules.suicide || friends.is_empty() {
+                return false
+            }
+            friends
+        } else {
+            Vec::new()
+        };
+        if self.history.rules.ko == KoRule::Simple {
+            return true
+        }
+        let mut hash = if suicide.is_empty() {
+            self.hash ^ zobrist::key(p, self.turn)
+        } else {
+            self.hash
+        };
+        for chain in captured.into_iter().chain(suicide.iter().map(|id| self.chains.chain(*id))) {
+            for s in &chain.stones {
+                hash ^= zobrist::key(Board::idx_to_coord(*s, self.board_size), chain.color);
+            }
+        }
+        !self.history.is_repetition(hash, self.turn)
+    }
+
+    pub fn play(&mut self, p: Point, s: Stone) {
+        // check if move is in history, if it is, use next_state
+        let point = Board::coord_to_idx(p, self.board_size);
+        self.stones[point] = s;
+        self.hash ^= zobrist::key(p, s.color);
+        self.chains.place(point, s.color);
+        let mut dead_groups = Vec::new();
+        for id in self.chains.adjacent_chains(point) {
+            let chain = self.chains.chain(id);
+            if chain.color != self.turn && chain.liberties.is_empty() {
+                dead_groups.push(self.remove_dead_chain(id));
+            }
+        }
+        // a suicide allowed by the rules, the opponent takes our stones
+        if let Some(id) = self.chains.chain_id(point) {
+            if self.chains.chain(id).liberties.is_empty() {
+                dead_groups.push(self.remove_dead_chain(id));
+            }
+        }
+        match self.history.push(Move {
+            player: self.turn,
+            index: Some(point),
+            groups: dead_groups,
+            hash: self.hash,
+            territory: Vec::new(),
+            setup: Vec::new(),
+            setup_node: false,
+            markup: Vec::new(),
+            comment: String::new(),
+            properties: Vec::new(),
+        }) {
+            Ok(_) => (),
+            Err(_) => panic!(), // show message something went wrong
+        }
+        self.last_move = Some(p);
+        self.turn.next();
+        self.current_move_number += 1;
+        self.update_ko();
+    }
+
+    // a ko exists when the last move took a single stone with a lone stone left in atari
+    fn update_ko(&mut self) {
+        let mov = &self.history.moves[self.history.current_index];
+        self.ko = match (mov.index, &mov.groups[..]) {
+            (Some(i), [group]) if group.stones.len() == 1 => {
+                match self.chains.chain_at(i) {
+                    Some(chain) if chain.stones.len() == 1 && chain.liberties.len() == 1 => Some(group.stones[0]),
+                    _ => None,
+                }
+            },
+            _ => None,
+        };
+    }
+
+    // AB/AW/AE: points are set without captures and the turn is given to to_move
+    // the current setup node, or an untouched root, takes the edits instead of a new node
+    fn editable_node(&self) -> Option<NodeIndex<u32>> {
+        let index = self.history.current_index;
+        let leaf = self.history.moves.neighbors(index).next().is_none();
+        if leaf && (index == NodeIndex::new(0) || self.history.moves[index].is_setup()) {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn edit(&mut self, index: usize, after: Option<Player>) {
+        match self.editable_node() {
+            Some(node) => {
+                let current = if self.stones[index].visible { Some(self.stones[index].color) } else { None };
+                self.set_point(index, current, after);
+                // a point is set once per node, and not at all once it is back to how the node found it
+                let mov = &mut self.history.moves[node];
+                match mov.setup.iter().position(|s| s.index == index) {
+                    Some(i) if mov.setup[i].before == after => {
+                        mov.setup.remove(i);
+                    },
+                    Some(i) => mov.setup[i].after = after,
+                    None if current != after => mov.setup.push(Setup { index, before: current, after }),
+                    None => (),
+                }
+                mov.hash = self.hash;
+                self.ko = None;
+            },
+            None => self.setup(vec![(index, after)], self.turn),
+        }
+    }
+
+    // swaps the current node's stones and turn for others, undoing an edit
+    pub fn replace_setup(&mut self, setup: Vec<Setup>, player: Player) {
+        let index = self.history.current_index;
+        let replaced = std::mem::replace(&mut self.history.moves[index].setup, setup);
+        for s in replaced.iter().rev() {
+            self.set_point(s.index, s.after, s.before);
+        }
+        for s in self.history.moves[index].setup.clone() {
+            self.set_point(s.index, s.before, s.after);
+        }
+        let mov = &mut self.history.moves[index];
+        mov.player = player;
+        mov.hash = self.hash;
+        self.turn = player.opponent();
+        self.ko = None;
+    }
+
+    pub fn can_set_turn(&self) -> bool {
+        self.editable_node().is_some()
+    }
+
+    // the side to move is only recorded in a setup node
+    pub fn set_turn(&mut self, player: Player) -> bool {
+        match self.editable_node() {
+            Some(index) => {
+                self.history.moves[index].player = player.opponent();
+                self.turn = player;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn setup(&mut self, stones: Vec<(usize, Option<Player>)>, to_move: Player) {
+        let setup = self.apply_setup(stones);
+        match self.history.push(Move {
+            player: to_move.opponent(),
+            index: None,
+            groups: Vec::new(),
+            hash: self.hash,
+            territory: Vec::new(),
+            setup,
+            setup_node: true,
+            markup: Vec::new(),
+            comment: String::new(),
+            properties: Vec::new(),
+        }) {
+            Ok(_) => (),
+            Err(_) => panic!(), // show message something went wrong
+        }
+        self.ko = None;
+        self.last_move = None;
+        self.turn = to_move;
+        self.current_move_number += 1;
+    }
+
+    // the root's setup is part of the starting position and is never reverted
+    pub fn setup_root(&mut self, stones: Vec<(usize, Option<Player>)>, to_move: Player) {
+        let setup = self.apply_setup(stones);
+        let root = &mut self.history.moves[NodeIndex::new(0)];
+        root.setup = setup;
+        root.hash = self.hash;
+        root.player = to_move.opponent();
+        self.turn = to_move;
+    }
+
+    fn apply_setup(&mut self, stones: Vec<(usize, Option<Player>)>) -> Vec<Setup> {
+        stones.into_iter().map(|(index, after)| {
+            let before = if self.stones[index].visible { Some(self.stones[index].color) } else { None };
+            self.set_point(index, before, after);
+            Setup { index, before, after }
+        }).collect()
+    }
+
+    fn set_point(&mut self, index: usize, before: Option<Player>, after: Option<Player>) {
+        let p = Board::idx_to_coord(index, self.board_size);
+        if let Some(color) = before {
+            self.hash ^= zobrist::key(p, color);
+            self.chains.remove(index);
+        }
+        self.stones[index] = match after {
+            Some(color) => {
+                self.hash ^= zobrist::key(p, color);
+                self.chains.place(index, color);
+                Stone::new(color)
+            },
+            None => Stone::default(),
+        };
+    }
+
+    fn remove_dead_chain(&mut self, id: usize) -> Group {
+        let chain = self.chains.remove_chain(id);
+        let mut group = Group {
+            stones: Vec::with_capacity(chain.stones.len()),
+            liberties: 0,
+            team: chain.color,
+        };
+        for i in chain.stones {
+            let p = Board::idx_to_coord(i, self.board_size);
+            self.stones[i] = Stone::default();
+            self.hash ^= zobrist::key(p, chain.color);
+            group.stones.push(p);
+        }
+        self.add_prisoners(chain.color.opponent(), group.stones.len() as i64);
+        group
+    }
+
+    fn add_prisoners(&mut self, player: Player, count: i64) {
+        let prisoners = match player {
+            Player::Black => &mut self.black_prisoners,
+            Player::White => &mut self.white_prisoners,
+        };
+        *prisoners = (*prisoners as i64 + count) as u32;
+    }
+
+    pub fn pass(&mut self) {
+        self.ko = None;
+        match self.history.push(Move {
+            player: self.turn,
+            index: None,
+            groups: Vec::new(),
+            hash: self.hash,
+            territory: Vec::new(),
+            setup: Vec::new(),
+            setup_node: false,
+            markup: Vec::new(),
+            comment: String::new(),
+            properties: Vec::new(),
+        }) {
+            Ok(_) => (),
+            Err(_) => panic!(), // show message something went wrong
+        }
+        self.last_move = None;
+        self.turn.next();
+        self.current_move_number += 1;
+    }
+
+
+    pub fn find_groups(&self) -> Vec<Group> {
+        self.chains.iter().map(|chain| Group {
+            stones: chain.stones.iter().map(|i| Board::idx_to_coord(*i, self.board_size)).collect(),
+            liberties: chain.liberties.len() as u64,
+            team: chain.color,
+        }).collect()
+    }
+
+    pub fn surrounding_points(p: Point, board_size: u32) -> impl Iterator<Item = Point> {
+        let x = p.x as i64;
+        let y = p.y as i64;
+        let board_size = board_size as i64;
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .filter_map(move |&(dx, dy)| {
+                if (x + dx) >= 0 && x + dx < board_size && (y + dy) >= 0 && y + dy < board_size {
+                    Some(Point::new((x + dx) as u32, (y + dy) as u32))
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub player: Player,
+    pub index: Option<usize>, // None is a pass
+    pub groups: Vec<Group>,
+    pub hash: u64, // zobrist hash of the position after this move
+    pub territory: Vec<(Player, usize)>, // TB/TW markup from scoring
+    pub setup: Vec<Setup>, // a setup node never holds a move
+    pub setup_node: bool, // a node without a move, even with no stones left to set, the root aside
+    pub markup: Vec<Markup>,
+    pub comment: String, // C, unescaped
+    pub properties: Vec<SgfToken>, // every other property of the sgf node, written back as read
+}
+
+impl Move {
+    pub fn is_pass(&self) -> bool {
+        self.index.is_none() && !self.is_setup()
+    }
+
+    // black stones only, white moves first against them
+    pub fn is_handicap(&self) -> bool {
+        !self.setup.is_empty() && self.setup.iter().all(|s| s.after == Some(Player::Black))
+    }
+
+    pub fn is_setup(&self) -> bool {
+        self.setup_node || !self.setup.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Setup {
+    pub index: usize,
+    pub before: Option<Player>, // kept to revert the node
+    pub after: Option<Player>, // None clears the point
+}
+
+impl From<History> for Board {
+    fn from(history: History) -> Self {
+        let mut board = Board::new(history.board_size);
+        let root = history.moves[NodeIndex::new(0)].clone();
+        for s in &root.setup {
+            board.set_point(s.index, s.before, s.after);
+        }
+        board.turn = root.player.opponent();
+        board.hash = root.hash;
+        board.history = history;
+        board
+    }
+}
+
+impl Board {
+    pub fn previous_state(&mut self) -> bool {
+        if let Some((previous_move, Move { index: played_move, groups: dead_stones, setup, .. })) = self.history.pop() {
+            for s in setup.iter().rev() {
+                self.set_point(s.index, s.after, s.before);
+            }
+            // the dead come back first, a suicide took the played stone with them
+            for group in dead_stones {
+                for p in &group.stones {
+                    let i = Board::coord_to_idx(*p, self.board_size);
+                    self.stones[i] = Stone::new(group.team);
+                    self.chains.place(i, group.team);
+                }
+                self.add_prisoners(group.team.opponent(), -(group.stones.len() as i64));
+            }
+            if let Some(played_move) = played_move {
+                self.stones[played_move] = Stone::default();
+                self.chains.remove(played_move);
+            }
+            // the node we are back to tells who was to play
+            self.turn = self.history.moves[self.history.current_index].player.opponent();
+            self.hash = self.history.moves[self.history.current_index].hash;
+            self.last_move = if let Some(idx) = previous_move {
+                Some(Board::idx_to_coord(idx, self.board_size))
+            } else {
+                None
+            };
+            self.current_move_number -= 1;
+            self.update_ko();
+
+            true
+        } else {
+            false
+        }
+    }
+
+    // back to the last node both lines share, then forward along the target's line
+    pub fn go_to(&mut self, target: NodeIndex<u32>) -> bool {
+        if self.history.moves.node_weight(target).is_none() {
+            return false
+        }
+        let path = self.history.path_to(target);
+        while !path.contains(&self.history.current_index) {
+            self.previous_state();
+        }
+        let start = path.iter().position(|i| *i == self.history.current_index).unwrap();
+        for pair in path[start..].windows(2) {
+            self.history.variation_picker.insert(pair[0], pair[1]);
+            self.next_state();
+        }
+        true
+    }
+
+    // drops the current node and everything played after it, the board goes back to its parent
+    pub fn delete_variation(&mut self) -> Option<Subtree> {
+        let index = self.history.current_index;
+        if index == NodeIndex::new(0) || !self.previous_state() {
+            return None
+        }
+        Some(self.history.remove_subtree(index))
+    }
+
+    pub fn next_state(&mut self) -> bool {
+        if let Some(Move { player, index: played_move, groups: dead_stones, hash, setup, .. }) = self.history.next() {
+            for s in &setup {
+                self.set_point(s.index, s.before, s.after);
+            }
+            if let Some(played_move) = played_move {
+                self.stones[played_move] = Stone::new(player);
+                self.chains.place(played_move, player);
+            }
+            for group in dead_stones {
+                if let Some(id) = self.chains.chain_id(Board::coord_to_idx(group.stones[0], self.board_size)) {
+                    self.chains.remove_chain(id);
+                }
+                for p in &group.stones {
+                    let i = Board::coord_to_idx(*p, self.board_size);
+                    self.stones[i] = Stone::default();
+                }
+                self.add_prisoners(group.team.opponent(), group.stones.len() as i64);
+            }
+            self.turn = player;
+            self.turn.next();
+            self.hash = hash;
+            self.last_move = played_move.map(|i| Board::idx_to_coord(i, self.board_size));
+            self.current_move_number += 1;
+            self.update_ko();
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::rules::Rules;
+
+    // the position at the end of the main line of an sgf game
+    pub(crate) fn board(sgf: &str) -> Board {
+        let mut history = History::try_from(sgf_parser::parse(sgf).unwrap()).unwrap();
+        history.current_index = NodeIndex::new(0);
+        let mut board = Board::from(history);
+        while board.next_state() {}
+        board
+    }
+
+    // a chain's color, stones and liberties
+    type ChainState = (Player, Vec<usize>, Vec<usize>);
+
+    // each point's chain
+    fn chains(board: &Board) -> Vec<Option<ChainState>> {
+        (0..board.stones.len()).map(|i| board.chains.chain_at(i).map(|chain| {
+            let mut stones = chain.stones.clone();
+            stones.sort_unstable();
+            let mut liberties = chain.liberties.iter().copied().collect::<Vec<_>>();
+            liberties.sort_unstable();
+            (chain.color, stones, liberties)
+        })).collect()
+    }
+
+    // black takes the ko, both pass and white takes back the first position
+    fn ko_retaken_after_passes(rules: Rules) -> bool {
+        let mut board = board("(;SZ[5]AB[ba][ab][bc]AW[ca][db][cc][bb])");
+        board.history.set_rules(rules);
+        board.play(Point::new(2, 1), Stone::black());
+        assert!(!board.is_legal(Point::new(1, 1)));
+        board.pass();
+        board.pass();
+        board.is_legal(Point::new(1, 1))
+    }
+
+    #[test]
+    fn simple_ko_is_cleared_by_a_pass() {
+        assert!(ko_retaken_after_passes(Rules::JAPANESE));
+    }
+
+    #[test]
+    fn superko_forbids_repeating_a_position() {
+        assert!(!ko_retaken_after_passes(Rules::CHINESE));
+        assert!(!ko_retaken_after_passes(Rules::AGA));
+    }
+
+    #[test]
+    fn chains_follow_play_undo_and_redo() {
+        let mut board = board("(;SZ[5]AB[ba][ab][bc]AW[ca][db][cc][bb])");
+        let before = chains(&board);
+        board.play(Point::new(2, 1), Stone::black());
+        let after = chains(&board);
+        assert!(after[Board::coord_to_idx(Point::new(1, 1), 5)].is_none());
+        assert_eq!(after[Board::coord_to_idx(Point::new(2, 1), 5)], Some((Player::Black, vec![11], vec![6])));
+
+        assert!(board.previous_state());
+        assert_eq!(chains(&board), before);
+        assert!(board.next_state());
+        assert_eq!(chains(&board), after);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use crate::Player;
+use crate::board::Board;
+
+#[derive(Debug, Clone)]
+pub struct Chain {
+    pub color: Player,
+    pub stones: Vec<usize>,
+    pub liberties: HashSet<usize>,
+}
+
+// Connected stones and their liberties, updated stone by stone so a move only
+// touches the chains around it instead of rescanning the whole board
+#[derive(Debug, Clone)]
+pub struct Chains {
+    board_size: u32,
+    owner: Vec<Option<usize>>, // id of the chain on each point
+    chains: Vec<Option<Chain>>,
+    free: Vec<usize>, // ids of removed chains, reused by the next ones
+}
+
+impl Chains {
+    pub fn new(board_size: u32) -> Self {
+        Self {
+            board_size,
+            owner: vec![None; (board_size * board_size) as usize],
+            chains: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn neighbours(&self, i: usize) -> impl Iterator<Item = usize> {
+        let board_size = self.board_size;
+        Board::surrounding_points(Board::idx_to_coord(i, board_size), board_size)
+            .map(move |p| Board::coord_to_idx(p, board_size))
+    }
+
+    pub fn chain_id(&self, i: usize) -> Option<usize> {
+        self.owner[i]
+    }
+
+    pub fn chain(&self, id: usize) -> &Chain {
+        self.chains[id].as_ref().expect("chain was removed")
+    }
+
+    pub fn chain_at(&self, i: usize) -> Option<&Chain> {
+        self.owner[i].map(|id| self.chain(id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Chain> {
+        self.chains.iter().flatten()
+    }
+
+    // every distinct chain touching the point
+    pub fn adjacent_chains(&self, i: usize) -> Vec<usize> {
+        let mut ids = Vec::with_capacity(4);
+        for n in self.neighbours(i) {
+            if let Some(id) = self.owner[n] {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+
+    // adds a stone and merges it with its friends, captures are left to the caller
+    pub fn place(&mut self, i: usize, color: Player) {
+        let mut liberties = HashSet::new();
+        let mut friends: Vec<usize> = Vec::new();
+        for n in self.neighbours(i).collect::<Vec<_>>() {
+            match self.owner[n] {
+                None => {
+                    liberties.insert(n);
+                },
+                Some(id) => {
+                    let chain = self.chains[id].as_mut().unwrap();
+                    chain.liberties.remove(&i);
+                    if chain.color == color && !friends.contains(&id) {
+                        friends.push(id);
+                    }
+                },
+            }
+        }
+
+        // the biggest friend absorbs the others so we relabel as few stones as possible
+        friends.sort_by_key(|id| std::cmp::Reverse(self.chain(*id).stones.len()));
+        let id = match friends.first() {
+            Some(id) => *id,
+            None => self.alloc(Chain {
+                color,
+                stones: Vec::new(),
+                liberties: HashSet::new(),
+            }),
+        };
+        for other in friends.iter().skip(1) {
+            let other_chain = self.chains[*other].take().unwrap();
+            self.free.push(*other);
+            for s in &other_chain.stones {
+                self.owner[*s] = Some(id);
+            }
+            let chain = self.chains[id].as_mut().unwrap();
+            chain.stones.extend(other_chain.stones);
+            chain.liberties.extend(other_chain.liberties);
+        }
+        self.owner[i] = Some(id);
+        let chain = self.chains[id].as_mut().unwrap();
+        chain.stones.push(i);
+        chain.liberties.extend(liberties);
+    }
+
+    // takes a whole chain off the board, its points become liberties of the chains around
+    pub fn remove_chain(&mut self, id: usize) -> Chain {
+        let chain = self.chains[id].take().expect("chain was removed");
+        self.free.push(id);
+        for s in &chain.stones {
+            self.owner[*s] = None;
+        }
+        for s in &chain.stones {
+            for n in self.neighbours(*s).collect::<Vec<_>>() {
+                if let Some(other) = self.owner[n] {
+                    self.chains[other].as_mut().unwrap().liberties.insert(*s);
+                }
+            }
+        }
+        chain
+    }
+
+    // takes a single stone off the board, what is left of its chain may be split
+    pub fn remove(&mut self, i: usize) {
+        if let Some(id) = self.owner[i] {
+            let chain = self.remove_chain(id);
+            for s in chain.stones.into_iter().filter(|s| *s != i) {
+                self.place(s, chain.color);
+            }
+        }
+    }
+
+    fn alloc(&mut self, chain: Chain) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.chains[id] = Some(chain);
+                id
+            },
+            None => {
+                self.chains.push(Some(chain));
+                self.chains.len() - 1
+            },
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use sgf_parser::{GameTree, SgfError, SgfErrorKind, SgfToken};
+
+use crate::history::{History, HistoryError};
+
+// The games of an sgf file, only the reviewed one is played out into a History
+#[derive(Debug, Clone, Default)]
+pub struct Collection {
+    pub games: Vec<GameTree>,
+    pub current: usize,
+}
+
+// What tells the games of a collection apart in a list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameSummary {
+    pub index: usize,
+    pub black: String,
+    pub white: String,
+    pub date: String,
+    pub result: String,
+}
+
+impl std::fmt::Display for GameSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. {} - {}", self.index + 1, self.black, self.white)?;
+        if !self.date.is_empty() {
+            write!(f, ", {}", self.date)?;
+        }
+        if !self.result.is_empty() {
+            write!(f, ", {}", self.result)?;
+        }
+        Ok(())
+    }
+}
+
+impl Collection {
+    pub fn parse(sgf: &str) -> Result<Self, SgfError> {
+        let games = split_games(sgf).into_iter()
+            .map(sgf_parser::parse)
+            .collect::<Result<Vec<GameTree>, SgfError>>()?;
+        if games.is_empty() {
+            return Err(SgfErrorKind::ParseError.into())
+        }
+        Ok(Self { games, current: 0 })
+    }
+
+    pub fn history(&self) -> Result<History, HistoryError> {
+        History::try_from(self.games[self.current].clone())
+    }
+
+    // puts the reviewed game back with its edits, before saving or opening another game
+    pub fn store(&mut self, history: &History) {
+        self.games[self.current] = history.into_game_tree();
+    }
+
+    pub fn summaries(&self) -> Vec<GameSummary> {
+        self.games.iter().enumerate().map(|(index, game)| {
+            let mut summary = GameSummary {
+                index,
+                black: String::from("?"),
+                white: String::from("?"),
+                date: String::new(),
+                result: String::new(),
+            };
+            for token in game.nodes.first().map(|n| n.tokens.as_slice()).unwrap_or_default() {
+                match token {
+                    SgfToken::PlayerName { color: sgf_parser::Color::Black, name } => summary.black = name.clone(),
+                    SgfToken::PlayerName { color: sgf_parser::Color::White, name } => summary.white = name.clone(),
+                    SgfToken::Date(date) => summary.date = date.clone(),
+                    SgfToken::Result(outcome) => summary.result = outcome_text(outcome),
+                    _ => (),
+                }
+            }
+            summary
+        }).collect()
+    }
+
+    // one game tree after the other, like it was read
+    pub fn to_sgf(&self) -> String {
+        self.games.iter()
+            .map(|game| -> String { game.into() })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl From<&History> for Collection {
+    fn from(history: &History) -> Self {
+        Self {
+            games: vec![history.into_game_tree()],
+            current: 0,
+        }
+    }
+}
+
+// the parser stops after the first game tree, the file is cut at each top level parenthesis
+fn split_games(sgf: &str) -> Vec<&str> {
+    let mut games = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut in_value = false;
+    let mut escaped = false;
+    for (i, c) in sgf.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_value => escaped = true,
+            '[' => in_value = true,
+            ']' => in_value = false,
+            _ if in_value => (),
+            '(' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            },
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    games.push(&sgf[start..=i]);
+                }
+            },
+            _ => (),
+        }
+    }
+    games
+}
+
+fn outcome_text(outcome: &sgf_parser::Outcome) -> String {
+    let color = |c: &sgf_parser::Color| match c {
+        sgf_parser::Color::Black => "B",
+        sgf_parser::Color::White => "W",
+    };
+    match outcome {
+        sgf_parser::Outcome::WinnerByResign(c) => format!("{}+R", color(c)),
+        sgf_parser::Outcome::WinnerByForfeit(c) => format!("{}+F", color(c)),
+        sgf_parser::Outcome::WinnerByTime(c) => format!("{}+T", color(c)),
+        sgf_parser::Outcome::WinnerByPoints(c, points) => format!("{}+{}", color(c), points),
+        sgf_parser::Outcome::Draw => String::from("Draw"),
+    }
+}
@@ -0,0 +1,815 @@
+use std::collections::HashMap;
+
+use sgf_parser::{GameTree, GameNode, SgfToken};
+use petgraph::prelude::*;
+// the app names nodes without depending on petgraph
+pub use petgraph::graph::NodeIndex;
+
+use crate::{Player, board::{Point, Stone}};
+use crate::board::{Board, Move};
+use crate::markup::{self, Markup};
+use crate::rules::{KoRule, Rules};
+
+// the column labels and GTP stop at 25 lines
+pub const MIN_BOARD_SIZE: u32 = 2;
+pub const MAX_BOARD_SIZE: u32 = 25;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    PushCurrentNodeNotFound,
+    UnsupportedBoardSize(u32, u32),
+    MoveOutsideBoard(u8, u8),
+}
+
+impl std::fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PushCurrentNodeNotFound => write!(f, "the current node is not in the history"),
+            Self::UnsupportedBoardSize(width, height) if width != height => {
+                write!(f, "non square boards are not supported ({}x{})", width, height)
+            },
+            Self::UnsupportedBoardSize(width, _) => {
+                write!(f, "board size {} is not supported, it must be from {} to {}", width, MIN_BOARD_SIZE, MAX_BOARD_SIZE)
+            },
+            Self::MoveOutsideBoard(x, y) => write!(f, "the move at {}:{} is outside the board", x, y),
+        }
+    }
+}
+
+
+// a variation cut out of the tree, with what puts it back as it was
+#[derive(Debug, Clone)]
+pub struct Subtree {
+    pub parent: NodeIndex<u32>,
+    siblings: Vec<NodeIndex<u32>>, // the parent's children with the variation, in their order
+    nodes: Vec<(NodeIndex<u32>, NodeIndex<u32>, Move)>, // each node with its parent, parents first and siblings in their order
+    picks: Vec<(NodeIndex<u32>, NodeIndex<u32>)>, // the variation picker's choices leading into or inside the variation
+}
+
+impl Subtree {
+    pub fn root(&self) -> NodeIndex<u32> {
+        self.nodes[0].0
+    }
+
+    // follows nodes put back under another index
+    pub fn rename(&mut self, renamed: &HashMap<NodeIndex<u32>, NodeIndex<u32>>) {
+        let rename = |i: &mut NodeIndex<u32>| if let Some(new) = renamed.get(i) {
+            *i = *new;
+        };
+        rename(&mut self.parent);
+        self.siblings.iter_mut().for_each(rename);
+        for (index, parent, _) in &mut self.nodes {
+            rename(index);
+            rename(parent);
+        }
+        for (from, to) in &mut self.picks {
+            rename(from);
+            rename(to);
+        }
+    }
+}
+
+//TODO: into sgf_parser::GameTree
+#[derive(Debug, Clone)]
+pub struct History {
+    pub moves: StableGraph<Move, (), Directed>, // stable so deleting a variation keeps the other indexes
+    pub current_index: NodeIndex<u32>,
+    pub variation_picker: HashMap<NodeIndex<u32>, NodeIndex<u32>>,
+    pub game_info: GameNode,
+    pub board_size: u32,
+    pub rules: Rules,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(19)
+    }
+}
+
+impl History {
+    pub fn new(board_size: u32) -> Self {
+        let mut graph = StableGraph::new();
+        graph.add_node(Move {
+            player: Player::White,
+            index: None,
+            groups: Vec::new(),
+            hash: 0,
+            territory: Vec::new(),
+            setup: Vec::new(),
+            setup_node: false,
+            markup: Vec::new(),
+            comment: String::new(),
+            properties: Vec::new(),
+        });
+
+        let game_info = GameNode {
+            tokens: vec![
+                SgfToken::Game(sgf_parser::Game::Go),
+                SgfToken::FileFormat(4),
+                SgfToken::Charset(sgf_parser::Encoding::UTF8),
+                SgfToken::Application {
+                    name: "AInalyzer".to_string(),
+                    version: "0.1.0".to_string(),
+                },
+                SgfToken::VariationDisplay {
+                    nodes: sgf_parser::DisplayNodes::Children,
+                    on_board_display: true,
+                },
+                SgfToken::Rule(sgf_parser::RuleSet::Japanese),
+                SgfToken::Size(board_size, board_size),
+                SgfToken::Komi(6.5),
+                SgfToken::PlayerName {
+                    color: sgf_parser::Color::Black,
+                    name: "Black".to_string(),
+                },
+                SgfToken::PlayerName {
+                    color: sgf_parser::Color::White,
+                    name: "White".to_string(),
+                },
+            ],
+        };
+
+        History {
+            moves: graph,
+            current_index: 0.into(),
+            variation_picker: HashMap::new(),
+            game_info,
+            board_size,
+            rules: Rules::JAPANESE,
+        }
+    }
+}
+
+impl TryFrom<GameTree> for History {
+    type Error = HistoryError;
+
+    // This is costly, playing out the whole game to create the history tree
+    // The sgf loading from must only contain valid moves
+    fn try_from(mut t: GameTree) -> Result<Self, Self::Error> {
+        log::debug!("start loading sgf");
+        let root = t.nodes.first_mut().unwrap();
+        let board_size = match root.tokens.iter().find_map(|t| match t {
+            SgfToken::Size(width, height) => Some((*width, *height)),
+            _ => None,
+        }) {
+            None => 19,
+            Some((width, height)) if width == height && (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&width) => width,
+            Some((width, height)) => return Err(HistoryError::UnsupportedBoardSize(width, height)),
+        };
+        // handicap and problem positions live in the root, they become the starting position
+        let setup = History::node_setup(root, board_size);
+        // without PL a handicap style setup gives the turn to white
+        let handicap = !setup.is_empty() && setup.iter().all(|(_, c)| *c == Some(Player::Black));
+        let to_move = History::node_player_to_move(root, if handicap { Player::White } else { Player::Black });
+        root.tokens.retain(|t| !History::is_setup_token(t));
+        let mut game_info = root.clone();
+        // the root's marks, comment and territory are read like any other node's
+        game_info.tokens.retain(|t| !History::is_node_token(t, true, board_size));
+        root.tokens.retain(|t| History::is_node_token(t, true, board_size));
+        let mut board = Board::new(board_size);
+        board.setup_root(setup, to_move);
+        board.history.game_info = game_info;
+        board.history.rules = board.history.rule_set().map(Rules::from).unwrap_or_default();
+        History::add_tree_to_history(t, &mut board, true)?;
+        board.history.current_index = 0.into();
+        // the file's first variation is the main line, not the last one read
+        board.history.variation_picker.clear();
+        log::debug!("finished loading sgf");
+        Ok(board.history)
+    }
+}
+
+impl History {
+
+    fn add_tree_to_history(tree: GameTree, board: &mut Board, root: bool) -> Result<usize, HistoryError> {
+        let mut counter = 0;
+        for (i, n) in tree.nodes.into_iter().enumerate() {
+            let setup = History::node_setup(&n, board.board_size);
+            let has_setup = !setup.is_empty();
+            let has_move = n.tokens.iter().any(|t| matches!(t, SgfToken::Move { .. }));
+            // a node without a move is kept apart like a setup, with its comment and marks, only the root's are the root's own
+            let setup_node = has_setup || !(has_move || root && i == 0);
+            if setup_node {
+                let to_move = History::node_player_to_move(&n, board.turn);
+                board.setup(setup, to_move);
+                counter += 1;
+            }
+            let markup: Vec<Markup> = n.tokens.iter()
+                .flat_map(|t| Markup::from_token(t, board.board_size))
+                .collect();
+            let properties: Vec<SgfToken> = n.tokens.iter()
+                .filter(|t| !History::is_node_token(t, setup_node, board.board_size))
+                .cloned()
+                .collect();
+            let mut territory = Vec::new();
+            let mut comment = None;
+            for t in n.tokens {
+                match t {
+                    SgfToken::Comment(value) => comment = Some(unescape_text(&value)),
+                    SgfToken::Unknown((identifier, value)) if identifier == "TB" || identifier == "TW" => {
+                        if let Some(p) = Point::from_sgf(&value) {
+                            let owner = if identifier == "TB" { Player::Black } else { Player::White };
+                            territory.push((owner, Board::coord_to_idx(p, board.board_size)));
+                        }
+                    },
+                    sgf_parser::SgfToken::Move { color, action } => {
+                        // the sgf is authoritative on who plays, passes and handicap don't always alternate
+                        board.turn = color.into();
+                        let size = board.board_size;
+                        match action {
+                            sgf_parser::Action::Move(x, y) if (1..=size).contains(&(x as u32)) && (1..=size).contains(&(y as u32)) => {
+                                let stone = Stone::new(board.turn);
+                                Board::play(board, Point::new(x as u32 - 1, y as u32 - 1), stone);
+                            },
+                            // FF[3] passes with tt, off the board up to 19x19
+                            sgf_parser::Action::Move(_, _) if size <= 19 => Board::pass(board),
+                            sgf_parser::Action::Move(x, y) => return Err(HistoryError::MoveOutsideBoard(x, y)),
+                            sgf_parser::Action::Pass => Board::pass(board),
+                        }
+                        counter += 1;
+                    },
+                    _ => (),
+                }
+            }
+            if !territory.is_empty() {
+                board.history.set_territory(territory);
+            }
+            if !markup.is_empty() {
+                let current = board.history.current_index;
+                board.history.moves[current].markup = markup;
+            }
+            if let Some(comment) = comment {
+                let current = board.history.current_index;
+                board.history.moves[current].comment = comment;
+            }
+            if !properties.is_empty() {
+                let current = board.history.current_index;
+                board.history.moves[current].properties.extend(properties);
+            }
+        }
+
+        // it's used but clippy can't see it
+        #[allow(unused_assignments)]
+        let mut counter2 = 0;
+        for v in tree.variations {
+            counter2 = History::add_tree_to_history(v, board, false)?;
+            for _ in 0..counter2 {
+                board.previous_state();
+            }
+        }
+        Ok(counter)
+    }
+
+    // AB/AW add stones and AE clears points, the parser leaves the compressed "aa:cc" lists invalid
+    fn node_setup(node: &GameNode, board_size: u32) -> Vec<(usize, Option<Player>)> {
+        node.tokens.iter().flat_map(|t| {
+            let (color, points) = match t {
+                SgfToken::Add { color, coordinate: (x, y) } => {
+                    let p = Point::new((*x as u32).wrapping_sub(1), (*y as u32).wrapping_sub(1));
+                    let points = if p.x < board_size && p.y < board_size {
+                        vec![Board::coord_to_idx(p, board_size)]
+                    } else {
+                        Vec::new()
+                    };
+                    (Some((*color).into()), points)
+                },
+                SgfToken::Unknown((identifier, value)) | SgfToken::Invalid((identifier, value)) => match identifier.as_str() {
+                    "AB" => (Some(Player::Black), markup::rectangle(value, board_size)),
+                    "AW" => (Some(Player::White), markup::rectangle(value, board_size)),
+                    "AE" => (None, markup::rectangle(value, board_size)),
+                    _ => return Vec::new(),
+                },
+                _ => return Vec::new(),
+            };
+            points.into_iter().map(|i| (i, color)).collect::<Vec<_>>()
+        }).fold(Vec::new(), |mut setup, (i, color)| {
+            // a point listed twice keeps its last property
+            setup.retain(|(j, _)| *j != i);
+            setup.push((i, color));
+            setup
+        })
+    }
+
+    // PL wins, without it the turn goes on
+    fn node_player_to_move(node: &GameNode, turn: Player) -> Player {
+        node.tokens.iter().find_map(|t| match t {
+            SgfToken::Unknown((identifier, value)) if identifier == "PL" => match value.as_str() {
+                "B" | "b" => Some(Player::Black),
+                "W" | "w" => Some(Player::White),
+                _ => None,
+            },
+            _ => None,
+        }).unwrap_or(turn)
+    }
+
+    fn is_setup_token(t: &SgfToken) -> bool {
+        match t {
+            SgfToken::Add { .. } => true,
+            SgfToken::Unknown((identifier, _)) => identifier == "AE" || identifier == "PL",
+            SgfToken::Invalid((identifier, _)) => identifier == "AB" || identifier == "AW" || identifier == "AE",
+            _ => false,
+        }
+    }
+
+    // properties read into the move itself, PL only counts along with a setup
+    fn is_node_token(t: &SgfToken, setup: bool, board_size: u32) -> bool {
+        match t {
+            SgfToken::Move { .. } | SgfToken::Comment(_) => true,
+            SgfToken::Unknown((identifier, _)) if identifier == "TB" || identifier == "TW" => true,
+            _ => (setup && History::is_setup_token(t)) || Markup::is_markup_token(t, board_size),
+        }
+    }
+
+    fn setup_tokens(&self, index: NodeIndex<u32>) -> Vec<SgfToken> {
+        let mov = &self.moves[index];
+        let mut tokens: Vec<SgfToken> = mov.setup.iter().map(|s| {
+            let p = Board::idx_to_coord(s.index, self.board_size);
+            match s.after {
+                Some(color) => SgfToken::Add {
+                    color: color.into(),
+                    coordinate: (p.x as u8 + 1, p.y as u8 + 1),
+                },
+                None => SgfToken::Unknown(("AE".to_string(), p.to_sgf())),
+            }
+        }).collect();
+        // the root's setup always says who starts, another node only when the turn doesn't go on from the one before
+        let turn = match self.parent(index) {
+            Some(parent) => self.moves[parent].player.opponent(),
+            None => Player::Black,
+        };
+        if mov.player.opponent() != turn || (index == 0.into() && !mov.setup.is_empty()) {
+            tokens.push(SgfToken::Unknown(("PL".to_string(), mov.player.opponent().to_string())));
+        }
+        tokens
+    }
+
+    pub fn rule_set(&self) -> Option<&sgf_parser::RuleSet> {
+        self.game_info.tokens.iter().find_map(|t| match t {
+            SgfToken::Rule(rules) => Some(rules),
+            _ => None,
+        })
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.replace_rules(rules, Some(rules.to_sgf()));
+    }
+
+    // the RU property can say more than the rules it is read into
+    pub fn replace_rules(&mut self, rules: Rules, rule_set: Option<sgf_parser::RuleSet>) {
+        self.rules = rules;
+        self.game_info.tokens.retain(|t| !matches!(t, SgfToken::Rule(_)));
+        self.game_info.tokens.extend(rule_set.map(SgfToken::Rule));
+    }
+
+    pub fn komi(&self) -> f32 {
+        self.game_info.tokens.iter().find_map(|t| match t {
+            SgfToken::Komi(komi) => Some(*komi),
+            _ => None,
+        }).unwrap_or(0.0)
+    }
+
+    pub fn result(&self) -> Option<sgf_parser::Outcome> {
+        self.game_info.tokens.iter().find_map(|t| match t {
+            SgfToken::Result(result) => Some(*result),
+            _ => None,
+        })
+    }
+
+    pub fn set_result(&mut self, result: Option<sgf_parser::Outcome>) {
+        self.game_info.tokens.retain(|t| !matches!(t, SgfToken::Result(_)));
+        self.game_info.tokens.extend(result.map(SgfToken::Result));
+    }
+
+    pub fn set_territory(&mut self, territory: Vec<(Player, usize)>) {
+        self.moves[self.current_index].territory = territory;
+    }
+
+    // root first, current node last
+    pub fn path_to_current(&self) -> Vec<NodeIndex<u32>> {
+        self.path_to(self.current_index)
+    }
+
+    pub fn path_to(&self, index: NodeIndex<u32>) -> Vec<NodeIndex<u32>> {
+        let mut path = vec![index];
+        while let Some(i) = self.moves.neighbors_directed(*path.last().unwrap(), Direction::Incoming).next() {
+            path.push(i);
+        }
+        path.reverse();
+        path
+    }
+
+    // moves from the root to the current node with their number, setup nodes aren't counted
+    pub fn numbered_moves(&self) -> Vec<(u32, &Move)> {
+        self.path_to_current().into_iter()
+            .skip(1)
+            .map(|i| &self.moves[i])
+            .filter(|mov| !mov.is_setup())
+            .enumerate()
+            .map(|(n, mov)| (n as u32 + 1, mov))
+            .collect()
+    }
+
+    // number of the last move before the current line leaves another one, 0 without any fork
+    pub fn branch_move_number(&self) -> u32 {
+        let mut number = 0;
+        let mut branch = 0;
+        let path = self.path_to_current();
+        for (i, index) in path.iter().enumerate() {
+            if *index != 0.into() && !self.moves[*index].is_setup() {
+                number += 1;
+            }
+            if i + 1 < path.len() && self.moves.neighbors(*index).count() > 1 {
+                branch = number;
+            }
+        }
+        branch
+    }
+
+    pub fn toggle_markup(&mut self, markup: Markup) {
+        markup::toggle(&mut self.moves[self.current_index].markup, markup);
+    }
+
+    pub fn next_label(&self) -> String {
+        markup::next_label(&self.moves[self.current_index].markup)
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.moves[self.current_index].comment
+    }
+
+    pub fn set_comment(&mut self, comment: String) {
+        self.moves[self.current_index].comment = comment;
+    }
+
+    pub fn into_game_tree(&self) -> sgf_parser::GameTree {
+        // 0 is always the root node
+        self.build_game_tree(0.into()).unwrap()
+    }
+
+    // a line without forks stays a single sequence, variations are written in the order they were added
+    fn build_game_tree(&self, index: NodeIndex<u32>) -> Option<GameTree> {
+        let mut sgf = GameTree::default();
+        let mut index = index;
+        loop {
+            sgf.nodes.push(self.build_node(index));
+            let children = self.children(index);
+            match children[..] {
+                [next] => index = next,
+                _ => {
+                    sgf.variations.extend(children.into_iter().filter_map(|n| self.build_game_tree(n)));
+                    return Some(sgf)
+                },
+            }
+        }
+    }
+
+    fn build_node(&self, index: NodeIndex<u32>) -> GameNode {
+        let mut node = if self.moves[index].is_setup() && index != 0.into() {
+            GameNode { tokens: self.setup_tokens(index) }
+        } else if index != 0.into() {
+            let action = match self.moves[index].index {
+                Some(i) => {
+                    let Point {mut x, mut y} = Board::idx_to_coord(i, self.board_size);
+                    x += 1;
+                    y += 1;
+                    sgf_parser::Action::Move(x as u8, y as u8)
+                },
+                None => sgf_parser::Action::Pass,
+            };
+            GameNode { tokens: vec![sgf_parser::SgfToken::Move {
+                color: match self.moves[index].player {
+                    Player::Black => sgf_parser::Color::Black,
+                    Player::White => sgf_parser::Color::White,
+                },
+                action,
+            }]}
+        } else {
+            let mut game_info = self.game_info.clone();
+            game_info.tokens.extend(self.setup_tokens(index));
+            game_info
+        };
+        for (owner, i) in &self.moves[index].territory {
+            let identifier = match owner {
+                Player::Black => "TB",
+                Player::White => "TW",
+            };
+            let p = Board::idx_to_coord(*i, self.board_size);
+            node.tokens.push(SgfToken::Unknown((identifier.to_string(), p.to_sgf())));
+        }
+        node.tokens.extend(self.moves[index].markup.iter().map(|m| m.to_token(self.board_size)));
+        node.tokens.extend(self.moves[index].properties.iter().cloned());
+        if !self.moves[index].comment.is_empty() {
+            node.tokens.push(SgfToken::Comment(escape_text(&self.moves[index].comment)));
+        }
+        node
+    }
+
+    pub fn push(&mut self, elem: Move) -> Result<(), HistoryError> {
+
+        let new_node = self.moves.add_node(elem);
+        // This check is there to prevent add_edge from panicking
+        if self.moves.node_weight(self.current_index).is_some() {
+            // if our current node already have outgoing neighbors
+            // we just created a variation and need to add it to variation_picker
+            if self.moves.neighbors(self.current_index).next().is_some() {
+                self.variation_picker.insert(self.current_index, new_node);
+            }
+            self.moves.add_edge(self.current_index, new_node, ());
+        } else {
+            return Err(HistoryError::PushCurrentNodeNotFound)
+        }
+        self.current_index = new_node;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<(Option<usize>, Move)> {
+        if self.current_index == 0.into() {
+            return None
+        }
+        // we check if the index exists
+        if self.moves.node_weight(self.current_index).is_some() {
+            let move_to_pop = self.moves[self.current_index].clone();
+            // We should ever have one incoming edge
+            let previous_move = self.moves.neighbors_directed(self.current_index, Direction::Incoming).next();
+            let previous_move = if let Some(i) = previous_move {
+                self.current_index = i;
+                if i == 0.into() {
+                    None
+                } else {
+                    self.moves[i].index
+                }
+            } else {
+                None
+            };
+            Some((previous_move, move_to_pop))
+        } else {
+            None
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Move> {
+        let next = self.next_index(self.current_index)?;
+        self.current_index = next;
+        Some(self.moves[next].clone())
+    }
+
+    // the variation picked at a fork, the main line otherwise
+    fn next_index(&self, index: NodeIndex<u32>) -> Option<NodeIndex<u32>> {
+        match self.variation_picker.get(&index) {
+            Some(next) => Some(*next),
+            None => self.children(index).first().copied(),
+        }
+    }
+
+    // the current line from the root to its last move, the way next would follow it
+    pub fn line(&self) -> Vec<NodeIndex<u32>> {
+        let mut line = self.path_to_current();
+        while let Some(next) = self.next_index(*line.last().unwrap()) {
+            line.push(next);
+        }
+        line
+    }
+
+    pub fn end_of_line(&self) -> NodeIndex<u32> {
+        *self.line().last().unwrap()
+    }
+
+    // move number n of the current line, counted like numbered_moves, the last one when the line is shorter
+    pub fn move_index(&self, n: u32) -> NodeIndex<u32> {
+        let line = self.line();
+        let mut number = 0;
+        for index in &line {
+            if *index != 0.into() && !self.moves[*index].is_setup() {
+                number += 1;
+            }
+            if number >= n {
+                return *index
+            }
+        }
+        *line.last().unwrap()
+    }
+
+    // the next node of the current line with several children
+    pub fn next_fork(&self) -> Option<NodeIndex<u32>> {
+        let line = self.line();
+        let current = line.iter().position(|i| *i == self.current_index).unwrap();
+        line[current + 1..].iter().copied().find(|i| self.moves.neighbors(*i).count() > 1)
+    }
+
+    pub fn previous_fork(&self) -> Option<NodeIndex<u32>> {
+        let path = self.path_to_current();
+        path[..path.len() - 1].iter().rev().copied().find(|i| self.moves.neighbors(*i).count() > 1)
+    }
+
+    // returns true if move is a variation choice
+    pub fn set_variation_to_move(&mut self, mov: Option<usize>) -> bool {
+        for e in self.moves.neighbors(self.current_index) {
+            if !self.moves[e].is_setup() && self.moves[e].index == mov {
+                self.variation_picker.insert(self.current_index, e);
+                return true
+            }
+        }
+        false
+    }
+
+    // walks back to the root looking for the position, the current node included
+    pub fn is_repetition(&self, hash: u64, player: Player) -> bool {
+        let mut index = Some(self.current_index);
+        while let Some(i) = index {
+            let mov = &self.moves[i];
+            if mov.hash == hash && match self.rules.ko {
+                KoRule::Simple => false,
+                KoRule::Positional => true,
+                // the node's player is the one who created the position, the other one is to move
+                KoRule::Situational => mov.player == player,
+            } {
+                return true
+            }
+            index = self.moves.neighbors_directed(i, Direction::Incoming).next();
+        }
+        false
+    }
+
+    // in the order they were added, the first one is the main line
+    pub fn children(&self, index: NodeIndex<u32>) -> Vec<NodeIndex<u32>> {
+        // petgraph gives the newest child first
+        let mut children: Vec<NodeIndex<u32>> = self.moves.neighbors(index).collect();
+        children.reverse();
+        children
+    }
+
+    pub fn parent(&self, index: NodeIndex<u32>) -> Option<NodeIndex<u32>> {
+        self.moves.neighbors_directed(index, Direction::Incoming).next()
+    }
+
+    // edges are listed newest first, adding them again in order sorts the children
+    pub fn set_children(&mut self, parent: NodeIndex<u32>, children: &[NodeIndex<u32>]) {
+        for child in children {
+            if let Some(edge) = self.moves.find_edge(parent, *child) {
+                self.moves.remove_edge(edge);
+            }
+        }
+        for child in children {
+            self.moves.add_edge(parent, *child, ());
+        }
+    }
+
+    // the node and everything played after it
+    pub fn subtree(&self, index: NodeIndex<u32>) -> Subtree {
+        let parent = self.parent(index).unwrap();
+        let mut nodes = vec![(index, parent, self.moves[index].clone())];
+        let mut i = 0;
+        while i < nodes.len() {
+            let node = nodes[i].0;
+            nodes.extend(self.children(node).into_iter().map(|child| (child, node, self.moves[child].clone())));
+            i += 1;
+        }
+        let picks = self.variation_picker.iter()
+            .filter(|(from, to)| nodes.iter().any(|(i, _, _)| i == *from || i == *to))
+            .map(|(from, to)| (*from, *to))
+            .collect();
+        Subtree { parent, siblings: self.children(parent), nodes, picks }
+    }
+
+    // the current node must be outside
+    pub fn remove_subtree(&mut self, index: NodeIndex<u32>) -> Subtree {
+        let subtree = self.subtree(index);
+        for (from, _) in &subtree.picks {
+            self.variation_picker.remove(from);
+        }
+        for (index, _, _) in &subtree.nodes {
+            self.moves.remove_node(*index);
+        }
+        subtree
+    }
+
+    // puts a removed variation back, the graph usually hands the freed indexes out again
+    // but another node may have taken one meanwhile, returns the nodes that got a new index
+    pub fn restore_subtree(&mut self, subtree: &Subtree) -> HashMap<NodeIndex<u32>, NodeIndex<u32>> {
+        let mut renamed = HashMap::new();
+        for (index, _, mov) in subtree.nodes.iter().rev() {
+            let added = self.moves.add_node(mov.clone());
+            if added != *index {
+                renamed.insert(*index, added);
+            }
+        }
+        let mut subtree = subtree.clone();
+        subtree.rename(&renamed);
+        for (index, parent, _) in &subtree.nodes {
+            self.moves.add_edge(*parent, *index, ());
+        }
+        self.set_children(subtree.parent, &subtree.siblings);
+        self.variation_picker.extend(subtree.picks);
+        renamed
+    }
+
+    // the first node of the current line that isn't its parent's only child
+    fn variation_start(&self) -> Option<NodeIndex<u32>> {
+        self.path_to_current().into_iter()
+            .rev()
+            .find(|i| self.parent(*i).is_some_and(|p| self.moves.neighbors(p).count() > 1))
+    }
+
+    // the current line becomes the first choice at every fork up to the root, false when it already was
+    pub fn promote_to_main_line(&mut self) -> bool {
+        let mut promoted = false;
+        let path = self.path_to_current();
+        for pair in path.windows(2) {
+            let (parent, child) = (pair[0], pair[1]);
+            let mut children = self.children(parent);
+            if children[0] != child {
+                children.retain(|c| *c != child);
+                children.insert(0, child);
+                self.set_children(parent, &children);
+                self.variation_picker.insert(parent, child);
+                promoted = true;
+            }
+        }
+        promoted
+    }
+
+    // moves the variation holding the current node one place before or after its siblings
+    pub fn shift_variation(&mut self, earlier: bool) -> bool {
+        let start = match self.variation_start() {
+            Some(start) => start,
+            None => return false,
+        };
+        let parent = self.parent(start).unwrap();
+        let mut children = self.children(parent);
+        let i = children.iter().position(|c| *c == start).unwrap();
+        let j = if earlier { i.checked_sub(1) } else { Some(i + 1) };
+        match j {
+            Some(j) if j < children.len() => {
+                children.swap(i, j);
+                self.set_children(parent, &children);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    pub fn get_possible_moves(&self) -> Vec<Option<usize>> {
+        self.moves.neighbors(self.current_index)
+            .map(|e| self.moves[e].index)
+            .collect()
+    }
+}
+
+// the parser keeps sgf text as written: a backslash escapes the next character
+// and a backslash before a line break joins both lines
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') | None => (),
+                Some(next) => text.push(next),
+            },
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+// the writer doesn't escape anything
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(sgf: &str) -> String {
+        let history = History::try_from(sgf_parser::parse(sgf).unwrap()).unwrap();
+        (&history.into_game_tree()).into()
+    }
+
+    #[test]
+    fn sgf_round_trip() {
+        // variations, a pass, a setup node, a comment node and markup
+        let sgf = "(;GM[1]FF[4]SZ[9]KM[6.5]RU[Chinese]AB[cc]PL[W];W[ee]C[center](;B[gg];W[];AW[aa]AE[cc]PL[B];C[a comment];B[dd]TR[dd])(;B[cg]))";
+        let saved = round_trip(sgf);
+        assert_eq!(round_trip(&saved), saved);
+        for part in ["AB[cc]", "C[center]", "W[]", "AW[aa]", "AE[cc]", "C[a comment]", "TR[dd]", "(;B[cg])"] {
+            assert!(saved.contains(part), "{} missing from {}", part, saved);
+        }
+    }
+
+    #[test]
+    fn handicap_turn_is_only_guessed_at_the_root() {
+        let history = History::try_from(sgf_parser::parse("(;SZ[9]AB[cc][gg];W[ee];AB[cg];B[gc])").unwrap()).unwrap();
+        assert_eq!(history.moves[NodeIndex::new(0)].player, Player::Black);
+        let saved = round_trip("(;SZ[9]AB[cc][gg];W[ee];AB[cg];B[gc])");
+        assert!(saved.contains("PL[W]"), "{}", saved);
+        assert_eq!(saved.matches("PL[").count(), 1, "{}", saved);
+    }
+
+    #[test]
+    fn unsupported_board_size() {
+        let game = sgf_parser::parse("(;SZ[30];B[aa])").unwrap();
+        assert_eq!(History::try_from(game).unwrap_err(), HistoryError::UnsupportedBoardSize(30, 30));
+    }
+}
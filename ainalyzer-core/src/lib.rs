@@ -0,0 +1,65 @@
+// Board state, rules and game records without any user interface, the GUI and
+// the engine bridge live in the application crate
+#[macro_use] extern crate lazy_static;
+
+pub mod board;
+pub mod chains;
+pub mod collection;
+pub mod history;
+pub mod influence;
+pub mod life;
+pub mod markup;
+pub mod rules;
+pub mod scoring;
+pub mod tactics;
+pub mod undo;
+pub mod zobrist;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Player {
+    Black,
+    White,
+}
+
+impl Player {
+    pub fn next(&mut self) {
+        match self {
+            Self::Black => *self = Self::White,
+            Self::White => *self = Self::Black,
+        }
+    }
+
+    pub fn opponent(self) -> Self {
+        match self {
+            Self::Black => Self::White,
+            Self::White => Self::Black,
+        }
+    }
+}
+
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Black => "B",
+            Self::White => "W",
+        })
+    }
+}
+
+impl From<sgf_parser::Color> for Player {
+    fn from(c: sgf_parser::Color) -> Self {
+        match c {
+            sgf_parser::Color::Black => Self::Black,
+            sgf_parser::Color::White => Self::White,
+        }
+    }
+}
+
+impl From<Player> for sgf_parser::Color {
+    fn from(p: Player) -> Self {
+        match p {
+            Player::Black => Self::Black,
+            Player::White => Self::White,
+        }
+    }
+}
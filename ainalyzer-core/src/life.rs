@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use crate::Player;
+use crate::board::{Board, Group, Point};
+
+// A maximal connected set of points without any stone of the color being checked
+struct Region {
+    borders: HashSet<usize>, // chains of the checked color around the region
+    vital: Vec<usize>, // chains having every empty point of the region as a liberty
+}
+
+// Benson's algorithm: the chains of this color that can't be captured,
+// even if the opponent plays every move and their owner keeps passing
+pub fn unconditionally_alive(board: &Board, color: Player) -> Vec<Group> {
+    let chains = &board.chains;
+    let points = board.stones.len();
+    let mut alive: HashSet<usize> = (0..points)
+        .filter_map(|i| chains.chain_id(i))
+        .filter(|id| chains.chain(*id).color == color)
+        .collect();
+
+    let mut regions = Vec::new();
+    let mut seen = vec![false; points];
+    for start in 0..points {
+        if seen[start] || chains.chain_at(start).is_some_and(|c| c.color == color) {
+            continue
+        }
+        let mut empty = Vec::new();
+        let mut borders = HashSet::new();
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(i) = stack.pop() {
+            if !board.stones[i].visible {
+                empty.push(i);
+            }
+            for n in chains.neighbours(i) {
+                match chains.chain_id(n) {
+                    Some(id) if chains.chain(id).color == color => {
+                        borders.insert(id);
+                    },
+                    _ => if !seen[n] {
+                        seen[n] = true;
+                        stack.push(n);
+                    },
+                }
+            }
+        }
+        let vital = borders.iter()
+            .copied()
+            .filter(|id| empty.iter().all(|i| chains.chain(*id).liberties.contains(i)))
+            .collect();
+        regions.push(Region { borders, vital });
+    }
+
+    // drop chains with less than two vital regions, then the regions they were keeping healthy
+    let mut healthy = vec![true; regions.len()];
+    loop {
+        let before = alive.len();
+        alive.retain(|id| regions.iter().zip(&healthy)
+            .filter(|(region, healthy)| **healthy && region.vital.contains(id))
+            .count() >= 2);
+        if alive.len() == before {
+            break
+        }
+        for (region, healthy) in regions.iter().zip(healthy.iter_mut()) {
+            if *healthy && !region.borders.iter().all(|id| alive.contains(id)) {
+                *healthy = false;
+            }
+        }
+    }
+
+    alive.into_iter().map(|id| {
+        let chain = chains.chain(id);
+        Group {
+            stones: chain.stones.iter().map(|i| Board::idx_to_coord(*i, board.board_size)).collect(),
+            liberties: chain.liberties.len() as u64,
+            team: chain.color,
+        }
+    }).collect()
+}
+
+// stones of both colors that no sequence of moves can capture
+pub fn alive_stones(board: &Board) -> HashSet<Point> {
+    [Player::Black, Player::White].into_iter()
+        .flat_map(|color| unconditionally_alive(board, color))
+        .flat_map(|group| group.stones)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::board;
+
+    #[test]
+    fn two_eyes_live() {
+        let board = board("(;SZ[5]AB[ba][da][ea][ab][bb][cb][db][eb])");
+        let alive = unconditionally_alive(&board, Player::Black);
+        assert_eq!(alive.len(), 1);
+        assert_eq!(alive[0].stones.len(), 8);
+    }
+
+    #[test]
+    fn false_eye_does_not_live() {
+        // the corner eye is bordered by two chains cut apart by the white stone
+        let board = board("(;SZ[5]AB[ba][da][ea][ab][ac][cb][db][eb]AW[bb])");
+        assert!(unconditionally_alive(&board, Player::Black).is_empty());
+        assert!(alive_stones(&board).is_empty());
+    }
+
+    #[test]
+    fn chains_sharing_their_eyes_live() {
+        // the lone stone between the eyes is only connected through them
+        let board = board("(;SZ[5]AB[ab][ba][ca][cb][cc][bc][bd][ad])");
+        let alive = unconditionally_alive(&board, Player::Black);
+        assert_eq!(alive.len(), 2);
+        assert_eq!(alive_stones(&board).len(), 8);
+    }
+}
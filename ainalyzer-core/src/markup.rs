@@ -0,0 +1,150 @@
+use sgf_parser::SgfToken;
+
+use crate::board::{Board, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Triangle,
+    Square,
+    Circle,
+    Cross,
+}
+
+impl Shape {
+    fn identifier(&self) -> &'static str {
+        match self {
+            Self::Triangle => "TR",
+            Self::Square => "SQ",
+            Self::Circle => "CR",
+            Self::Cross => "MA",
+        }
+    }
+}
+
+// TR, SQ, CR, MA, LB, AR and LN, points are board indexes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Markup {
+    Shape(Shape, usize),
+    Label(usize, String),
+    Arrow(usize, usize),
+    Line(usize, usize),
+}
+
+impl Markup {
+    // a single sgf value can hold a whole rectangle of shapes
+    pub fn from_token(token: &SgfToken, board_size: u32) -> Vec<Markup> {
+        let index = |(x, y): (u8, u8)| {
+            let p = Point::new(x as u32 - 1, y as u32 - 1);
+            if p.x < board_size && p.y < board_size {
+                Some(Board::coord_to_idx(p, board_size))
+            } else {
+                None
+            }
+        };
+        match token {
+            SgfToken::Triangle { coordinate } => index(*coordinate).map(|i| Markup::Shape(Shape::Triangle, i)).into_iter().collect(),
+            SgfToken::Square { coordinate } => index(*coordinate).map(|i| Markup::Shape(Shape::Square, i)).into_iter().collect(),
+            SgfToken::Label { label, coordinate } => index(*coordinate).map(|i| Markup::Label(i, label.clone())).into_iter().collect(),
+            // the parser reads CR as a copyright, the real one is CP
+            SgfToken::Copyright(value) => Markup::shapes(Shape::Circle, value, board_size),
+            SgfToken::Unknown((identifier, value)) | SgfToken::Invalid((identifier, value)) => match identifier.as_str() {
+                "TR" => Markup::shapes(Shape::Triangle, value, board_size),
+                "SQ" => Markup::shapes(Shape::Square, value, board_size),
+                "CR" => Markup::shapes(Shape::Circle, value, board_size),
+                "MA" => Markup::shapes(Shape::Cross, value, board_size),
+                "AR" | "LN" => match Markup::points(value, board_size)[..] {
+                    [from, to] if identifier == "AR" => vec![Markup::Arrow(from, to)],
+                    [from, to] => vec![Markup::Line(from, to)],
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn is_markup_token(token: &SgfToken, board_size: u32) -> bool {
+        !Markup::from_token(token, board_size).is_empty()
+    }
+
+    pub fn to_token(&self, board_size: u32) -> SgfToken {
+        let sgf = |i: usize| Board::idx_to_coord(i, board_size).to_sgf();
+        match self {
+            Markup::Shape(shape, i) => SgfToken::Unknown((shape.identifier().to_string(), sgf(*i))),
+            Markup::Label(i, label) => {
+                let p = Board::idx_to_coord(*i, board_size);
+                SgfToken::Label { label: label.clone(), coordinate: (p.x as u8 + 1, p.y as u8 + 1) }
+            },
+            Markup::Arrow(from, to) => SgfToken::Unknown(("AR".to_string(), format!("{}:{}", sgf(*from), sgf(*to)))),
+            Markup::Line(from, to) => SgfToken::Unknown(("LN".to_string(), format!("{}:{}", sgf(*from), sgf(*to)))),
+        }
+    }
+
+    // the point of a shape or a label
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Markup::Shape(_, i) | Markup::Label(i, _) => Some(*i),
+            Markup::Arrow(..) | Markup::Line(..) => None,
+        }
+    }
+
+    // clicking again with the same tool removes the mark, whatever the label says
+    fn same_mark(&self, other: &Markup) -> bool {
+        match (self, other) {
+            (Markup::Label(i, _), Markup::Label(j, _)) => i == j,
+            _ => self == other,
+        }
+    }
+
+    fn shapes(shape: Shape, value: &str, board_size: u32) -> Vec<Markup> {
+        rectangle(value, board_size).into_iter()
+            .map(|i| Markup::Shape(shape, i))
+            .collect()
+    }
+
+    fn points(value: &str, board_size: u32) -> Vec<usize> {
+        value.split(':')
+            .filter_map(Point::from_sgf)
+            .filter(|p| p.x < board_size && p.y < board_size)
+            .map(|p| Board::coord_to_idx(p, board_size))
+            .collect()
+    }
+}
+
+// "aa:cc" is the rectangle between both corners, the points off the board are left out
+pub fn rectangle(value: &str, board_size: u32) -> Vec<usize> {
+    let corners: Vec<Point> = value.split(':').filter_map(Point::from_sgf).collect();
+    let (from, to) = match corners[..] {
+        [p] => (p, p),
+        [a, b] => (a, b),
+        _ => return Vec::new(),
+    };
+    let mut points = Vec::new();
+    for x in from.x.min(to.x)..=from.x.max(to.x).min(board_size.saturating_sub(1)) {
+        for y in from.y.min(to.y)..=from.y.max(to.y).min(board_size.saturating_sub(1)) {
+            points.push(Board::coord_to_idx(Point::new(x, y), board_size));
+        }
+    }
+    points
+}
+
+// adds the mark, or removes it when it is already there, a point holds a single shape or label
+pub fn toggle(marks: &mut Vec<Markup>, markup: Markup) {
+    let before = marks.len();
+    marks.retain(|m| !m.same_mark(&markup));
+    if marks.len() != before {
+        return
+    }
+    if let Some(i) = markup.index() {
+        marks.retain(|m| m.index() != Some(i));
+    }
+    marks.push(markup);
+}
+
+// the first capital letter not used by a label yet
+pub fn next_label(marks: &[Markup]) -> String {
+    ('A'..='Z')
+        .map(|c| c.to_string())
+        .find(|label| !marks.iter().any(|m| matches!(m, Markup::Label(_, l) if l == label)))
+        .unwrap_or_else(|| String::from("?"))
+}
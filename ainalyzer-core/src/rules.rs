@@ -0,0 +1,177 @@
+use sgf_parser::RuleSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KoRule {
+    // only the immediate recapture is forbidden
+    Simple,
+    // a move can't recreate any previous board position
+    Positional,
+    // a move can't recreate a previous board position with the same player to move
+    Situational,
+}
+
+impl KoRule {
+    pub const ALL: [KoRule; 3] = [KoRule::Simple, KoRule::Positional, KoRule::Situational];
+
+    fn engine_name(&self) -> &'static str {
+        match self {
+            Self::Simple => "SIMPLE",
+            Self::Positional => "POSITIONAL",
+            Self::Situational => "SITUATIONAL",
+        }
+    }
+}
+
+impl std::fmt::Display for KoRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Simple => "Simple ko",
+            Self::Positional => "Positional superko",
+            Self::Situational => "Situational superko",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counting {
+    // territory and prisoners
+    Territory,
+    // territory and stones on the board
+    Area,
+    // territory and prisoners, every pass gives a prisoner to the opponent
+    Aga,
+}
+
+impl std::fmt::Display for Counting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Territory => "Territory",
+            Self::Area => "Area",
+            Self::Aga => "AGA",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub name: &'static str,
+    pub ko: KoRule,
+    // multi-stone suicide, a lone stone can never kill itself
+    pub suicide: bool,
+    pub counting: Counting,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::JAPANESE
+    }
+}
+
+impl Rules {
+    pub const JAPANESE: Rules = Rules { name: "Japanese", ko: KoRule::Simple, suicide: false, counting: Counting::Territory };
+    pub const CHINESE: Rules = Rules { name: "Chinese", ko: KoRule::Positional, suicide: false, counting: Counting::Area };
+    pub const AGA: Rules = Rules { name: "AGA", ko: KoRule::Situational, suicide: false, counting: Counting::Aga };
+    pub const NEW_ZEALAND: Rules = Rules { name: "New Zealand", ko: KoRule::Situational, suicide: true, counting: Counting::Area };
+    pub const TROMP_TAYLOR: Rules = Rules { name: "Tromp-Taylor", ko: KoRule::Positional, suicide: true, counting: Counting::Area };
+    pub const ING: Rules = Rules { name: "Ing", ko: KoRule::Positional, suicide: true, counting: Counting::Area };
+
+    pub const ALL: [Rules; 6] = [Rules::JAPANESE, Rules::CHINESE, Rules::AGA, Rules::NEW_ZEALAND, Rules::TROMP_TAYLOR, Rules::ING];
+
+    // a preset when one plays the same, custom rules otherwise
+    pub fn find(ko: KoRule, suicide: bool, counting: Counting) -> Rules {
+        Rules::ALL.into_iter()
+            .find(|r| r.ko == ko && r.suicide == suicide && r.counting == counting)
+            .unwrap_or(Rules { name: "Custom", ko, suicide, counting })
+    }
+
+    pub fn with_ko(self, ko: KoRule) -> Rules {
+        if ko == self.ko {
+            return self
+        }
+        Rules::find(ko, self.suicide, self.counting)
+    }
+
+    // custom rules are written in KataGo's short format, there is no name for them
+    pub fn to_sgf(&self) -> RuleSet {
+        match self.name {
+            "Custom" => RuleSet::Unknown(self.engine_rules()),
+            "Chinese" => RuleSet::Chinese,
+            "AGA" => RuleSet::AGA,
+            "New Zealand" => RuleSet::NZ,
+            "Japanese" => RuleSet::Japanese,
+            "Ing" => RuleSet::GOE,
+            name => RuleSet::Unknown(name.to_string()),
+        }
+    }
+
+    // KataGo's short rules format for kata-set-rules
+    pub fn engine_rules(&self) -> String {
+        let (score, tax) = match self.counting {
+            Counting::Territory => ("TERRITORY", "SEKI"),
+            // AGA counting gives the same result as area with pass stones
+            Counting::Area | Counting::Aga => ("AREA", "NONE"),
+        };
+        format!("ko{}score{}tax{}sui{}", self.ko.engine_name(), score, tax, self.suicide as u8)
+    }
+
+    // the short format as written by engine_rules, AGA counting is read back as area
+    fn from_engine_rules(rules: &str) -> Option<Rules> {
+        let rules = rules.to_uppercase();
+        let (ko, rest) = rules.strip_prefix("KO")?.split_once("SCORE")?;
+        let (score, rest) = rest.split_once("TAX")?;
+        let (_, suicide) = rest.split_once("SUI")?;
+        let ko = KoRule::ALL.into_iter().find(|k| k.engine_name() == ko)?;
+        let counting = match score {
+            "TERRITORY" => Counting::Territory,
+            "AREA" => Counting::Area,
+            _ => return None,
+        };
+        let suicide = match suicide {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        };
+        Some(Rules::find(ko, suicide, counting))
+    }
+}
+
+impl From<&RuleSet> for Rules {
+    fn from(rules: &RuleSet) -> Self {
+        match rules {
+            RuleSet::Japanese => Rules::JAPANESE,
+            RuleSet::Chinese => Rules::CHINESE,
+            RuleSet::AGA => Rules::AGA,
+            RuleSet::NZ => Rules::NEW_ZEALAND,
+            RuleSet::GOE => Rules::ING,
+            // the parser only knows the exact spelling of the standard names
+            RuleSet::Unknown(name) => Rules::from_engine_rules(name).unwrap_or(match name.to_lowercase().as_str() {
+                "chinese" => Rules::CHINESE,
+                "aga" => Rules::AGA,
+                "nz" | "new zealand" => Rules::NEW_ZEALAND,
+                "goe" | "ing" => Rules::ING,
+                name if name.starts_with("tromp") => Rules::TROMP_TAYLOR,
+                _ => Rules::JAPANESE,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_ko_rule_is_saved() {
+        let rules = Rules::JAPANESE.with_ko(KoRule::Situational);
+        assert_eq!(rules.name, "Custom");
+        assert_eq!(Rules::from(&rules.to_sgf()), rules);
+        // back to the preset's own ko
+        assert_eq!(rules.with_ko(KoRule::Simple), Rules::JAPANESE);
+    }
+}
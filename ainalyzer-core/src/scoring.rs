@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use crate::Player;
+use crate::board::{Board, Point};
+use crate::rules::Counting;
+
+#[derive(Debug, Clone)]
+pub struct Score {
+    pub counting: Counting,
+    pub komi: f32,
+    pub black_territory: u32,
+    pub white_territory: u32,
+    pub black_stones: u32,
+    pub white_stones: u32,
+    // stones taken by each player, dead stones included
+    pub black_prisoners: u32,
+    pub white_prisoners: u32,
+    pub ownership: Vec<Option<Player>>, // indexed like Board::stones
+    // empty points and dead stones counted for a player, saved as TB/TW
+    pub territory: Vec<(Player, usize)>,
+}
+
+impl Score {
+    pub fn new(board: &Board, dead_stones: &HashSet<Point>) -> Self {
+        let board_size = board.board_size;
+        let counting = board.history.rules.counting;
+        let mut score = Self {
+            counting,
+            komi: board.history.komi(),
+            black_territory: 0,
+            white_territory: 0,
+            black_stones: 0,
+            white_stones: 0,
+            black_prisoners: board.black_prisoners,
+            white_prisoners: board.white_prisoners,
+            ownership: vec![None; board.stones.len()],
+            territory: Vec::new(),
+        };
+
+        // every pass gives a stone to the opponent
+        if counting == Counting::Aga {
+            for index in board.history.path_to_current().into_iter().skip(1) {
+                let mov = &board.history.moves[index];
+                if mov.is_pass() {
+                    match mov.player {
+                        Player::Black => score.white_prisoners += 1,
+                        Player::White => score.black_prisoners += 1,
+                    }
+                }
+            }
+        }
+
+        // stones left on the board
+        let is_empty = |i: usize| !board.stones[i].visible || dead_stones.contains(&Board::idx_to_coord(i, board_size));
+        for (i, stone) in board.stones.iter().enumerate() {
+            if !stone.visible {
+                continue
+            }
+            if is_empty(i) {
+                match stone.color {
+                    Player::Black => score.white_prisoners += 1,
+                    Player::White => score.black_prisoners += 1,
+                }
+            } else {
+                score.ownership[i] = Some(stone.color);
+                match stone.color {
+                    Player::Black => score.black_stones += 1,
+                    Player::White => score.white_stones += 1,
+                }
+            }
+        }
+
+        // empty regions bordered by a single color are its territory,
+        // counting territory only the ones around settled groups
+        let unsettled = if counting == Counting::Territory {
+            unsettled_chains(board, dead_stones)
+        } else {
+            HashSet::new()
+        };
+        let mut seen = vec![false; board.stones.len()];
+        for start in 0..board.stones.len() {
+            if seen[start] || !is_empty(start) {
+                continue
+            }
+            let mut region = Vec::new();
+            let mut borders = HashSet::new();
+            let mut settled = true;
+            let mut stack = vec![start];
+            seen[start] = true;
+            while let Some(i) = stack.pop() {
+                region.push(i);
+                for p in Board::surrounding_points(Board::idx_to_coord(i, board_size), board_size) {
+                    let j = Board::coord_to_idx(p, board_size);
+                    if is_empty(j) {
+                        if !seen[j] {
+                            seen[j] = true;
+                            stack.push(j);
+                        }
+                    } else {
+                        borders.insert(board.stones[j].color);
+                        if let Some(id) = board.chains.chain_id(j) {
+                            settled &= !unsettled.contains(&id);
+                        }
+                    }
+                }
+            }
+            if borders.len() == 1 && settled {
+                let owner = *borders.iter().next().unwrap();
+                match owner {
+                    Player::Black => score.black_territory += region.len() as u32,
+                    Player::White => score.white_territory += region.len() as u32,
+                }
+                for i in region {
+                    score.ownership[i] = Some(owner);
+                    score.territory.push((owner, i));
+                }
+            }
+        }
+
+        score
+    }
+
+    pub fn black(&self) -> f32 {
+        (self.black_territory + match self.counting {
+            Counting::Territory | Counting::Aga => self.black_prisoners,
+            Counting::Area => self.black_stones,
+        }) as f32
+    }
+
+    pub fn white(&self) -> f32 {
+        (self.white_territory + match self.counting {
+            Counting::Territory | Counting::Aga => self.white_prisoners,
+            Counting::Area => self.white_stones,
+        }) as f32 + self.komi
+    }
+
+    pub fn result(&self) -> sgf_parser::Outcome {
+        let difference = self.black() - self.white();
+        if difference > 0.0 {
+            sgf_parser::Outcome::WinnerByPoints(sgf_parser::Color::Black, difference)
+        } else if difference < 0.0 {
+            sgf_parser::Outcome::WinnerByPoints(sgf_parser::Color::White, -difference)
+        } else {
+            sgf_parser::Outcome::Draw
+        }
+    }
+}
+
+// chains in atari and chains in seki, sharing a liberty that neither side
+// can fill without putting itself in atari
+fn unsettled_chains(board: &Board, dead_stones: &HashSet<Point>) -> HashSet<usize> {
+    let chains = &board.chains;
+    let alive = |id: &usize| !dead_stones.contains(&Board::idx_to_coord(chains.chain(*id).stones[0], board.board_size));
+    let mut unsettled: HashSet<usize> = (0..board.stones.len())
+        .filter_map(|i| chains.chain_id(i))
+        .filter(|id| alive(id) && chains.chain(*id).liberties.len() == 1)
+        .collect();
+    for i in 0..board.stones.len() {
+        if chains.chain_id(i).is_some() {
+            continue
+        }
+        let around: Vec<usize> = chains.adjacent_chains(i).into_iter().filter(alive).collect();
+        let fills_into_atari = |color: Player| {
+            let friends: Vec<usize> = around.iter().copied().filter(|id| chains.chain(*id).color == color).collect();
+            if friends.is_empty() {
+                return false
+            }
+            let mut liberties: HashSet<usize> = chains.neighbours(i).filter(|n| chains.chain_id(*n).is_none()).collect();
+            for id in friends {
+                liberties.extend(chains.chain(id).liberties.iter().filter(|l| **l != i));
+            }
+            liberties.len() <= 1
+        };
+        if fills_into_atari(Player::Black) && fills_into_atari(Player::White) {
+            unsettled.extend(around);
+        }
+    }
+    unsettled
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let difference = self.black() - self.white();
+        let result = if difference > 0.0 {
+            format!("B+{}", difference)
+        } else if difference < 0.0 {
+            format!("W+{}", -difference)
+        } else {
+            "Draw".to_string()
+        };
+        write!(f, "{} ({} counting, B: {} W: {})", result, self.counting, self.black(), self.white())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::board;
+    use crate::rules::Rules;
+
+    // black walls off two columns, white one, a black stone is left dead in white's area
+    const GAME: &str = "(;SZ[5]KM[0.5]AB[ca][cb][cc][cd][ce][ea]AW[da][db][dc][dd][de])";
+
+    fn score(rules: Rules) -> Score {
+        let mut board = board(GAME);
+        board.history.set_rules(rules);
+        Score::new(&board, &HashSet::from([Point::new(4, 0)]))
+    }
+
+    #[test]
+    fn area_counts_stones_and_territory() {
+        let score = score(Rules::CHINESE);
+        assert_eq!((score.black(), score.white()), (15.0, 10.5));
+        assert_eq!(score.result(), sgf_parser::Outcome::WinnerByPoints(sgf_parser::Color::Black, 4.5));
+    }
+
+    #[test]
+    fn seki_eyes_are_not_territory() {
+        // the top groups share the point between their eyes, the bottom one lives with two eyes
+        let mut board = board("(;SZ[5]KM[0.5]AB[ba][ab][bb][cb][ad][bd][cd][dd][ed][be][ce][de]AW[da][db][eb][ac][bc][cc][dc][ec])");
+        board.history.set_rules(Rules::JAPANESE);
+        let score = Score::new(&board, &HashSet::new());
+        assert_eq!((score.black_territory, score.white_territory), (2, 0));
+        assert_eq!(score.ownership[Board::coord_to_idx(Point::new(0, 0), 5)], None);
+
+        // their eyes are points counting the area
+        board.history.set_rules(Rules::CHINESE);
+        let score = Score::new(&board, &HashSet::new());
+        assert_eq!((score.black_territory, score.white_territory), (3, 1));
+    }
+
+    #[test]
+    fn territory_counts_prisoners() {
+        let score = score(Rules::JAPANESE);
+        assert_eq!((score.black(), score.white()), (10.0, 6.5));
+        assert_eq!(score.result(), sgf_parser::Outcome::WinnerByPoints(sgf_parser::Color::Black, 3.5));
+    }
+}
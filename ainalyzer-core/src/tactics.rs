@@ -0,0 +1,314 @@
+use crate::Player;
+use crate::board::{Board, Group, Point};
+use std::collections::HashSet;
+
+// a ladder running across the whole board is far below this
+const MAX_LADDER_DEPTH: usize = 200;
+// positions read for one ladder before giving up, a plain ladder needs a few per line it runs
+const MAX_LADDER_NODES: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct Ladder {
+    pub stones: Vec<Point>,
+    // the atari starting the ladder
+    pub start: Point,
+    // the chasing side captures the chain, None when the reading gave up
+    pub works: Option<bool>,
+}
+
+// What can be read without the engine: chains in atari and the ladders the player to move can start
+#[derive(Debug, Clone, Default)]
+pub struct Tactics {
+    pub atari: Vec<Group>,
+    pub ladders: Vec<Ladder>,
+}
+
+impl Tactics {
+    pub fn read(board: &Board) -> Self {
+        let mut reader = Reader::new(board);
+        let mut tactics = Tactics::default();
+        for group in board.find_groups() {
+            match group.liberties {
+                1 => tactics.atari.push(group),
+                2 if group.team != board.turn => {
+                    let i = Board::coord_to_idx(group.stones[0], board.board_size);
+                    if let Some((start, works)) = reader.ladder(i) {
+                        tactics.ladders.push(Ladder {
+                            stones: group.stones,
+                            start: Board::idx_to_coord(start, board.board_size),
+                            works,
+                        });
+                    }
+                },
+                _ => (),
+            }
+        }
+        tactics
+    }
+}
+
+// a move leaving its own chain with a single liberty, captures excepted as they include taking a ko
+pub fn is_self_atari(board: &Board, p: Point) -> bool {
+    let i = Board::coord_to_idx(p, board.board_size);
+    if board.chains.chain_id(i).is_some() {
+        return false
+    }
+    let captures = board.chains.adjacent_chains(i).into_iter()
+        .map(|id| board.chains.chain(id))
+        .any(|chain| chain.color != board.turn && chain.liberties.len() == 1);
+    if captures {
+        return false
+    }
+    // nothing is captured, the new chain has the point's liberties and the ones of the chains it joins
+    let mut liberties: HashSet<usize> = board.chains.neighbours(i)
+        .filter(|n| board.chains.chain_id(*n).is_none())
+        .collect();
+    for id in board.chains.adjacent_chains(i) {
+        let chain = board.chains.chain(id);
+        if chain.color == board.turn {
+            liberties.extend(chain.liberties.iter().filter(|l| **l != i));
+        }
+    }
+    // no liberty at all is a suicide, not an atari
+    liberties.len() == 1
+}
+
+// the reading ran out of positions
+struct GaveUp;
+
+// Reads sequences on a copy of the stones, each move is taken back once read. The ko is ignored
+struct Reader {
+    stones: Vec<Option<Player>>,
+    neighbours: Vec<Vec<usize>>,
+    played: Vec<(usize, Vec<usize>)>, // each move read with the stones it captured
+    // flood fills mark the points they reach with the current generation
+    marks: Vec<u32>,
+    generation: u32,
+    nodes: usize,
+}
+
+impl Reader {
+    fn new(board: &Board) -> Self {
+        let board_size = board.board_size;
+        Self {
+            stones: board.stones.iter().map(|s| if s.visible { Some(s.color) } else { None }).collect(),
+            neighbours: (0..board.stones.len())
+                .map(|i| Board::surrounding_points(Board::idx_to_coord(i, board_size), board_size)
+                    .map(|p| Board::coord_to_idx(p, board_size))
+                    .collect())
+                .collect(),
+            played: Vec::new(),
+            marks: vec![0; board.stones.len()],
+            generation: 0,
+            nodes: 0,
+        }
+    }
+
+    // the atari capturing the chain at i whatever it does, or else the one starting a ladder it gets out of
+    fn ladder(&mut self, i: usize) -> Option<(usize, Option<bool>)> {
+        self.nodes = 0;
+        let works = match self.ladder_atari(i, 0) {
+            Ok(Some(m)) => return Some((m, Some(true))),
+            Ok(None) => Some(false),
+            Err(GaveUp) => None,
+        };
+        self.chase(i).map(|m| (m, works))
+    }
+
+    // an atari the chain can only answer by running into the next one
+    fn chase(&mut self, i: usize) -> Option<usize> {
+        let color = self.stones[i]?;
+        self.liberties(i).into_iter().find(|m| {
+            if !self.play(*m, color.opponent()) {
+                return false
+            }
+            let run = self.liberties(i);
+            let chased = run.len() == 1 && self.play(run[0], color) && {
+                let liberties = self.liberties(i).len();
+                self.undo();
+                liberties == 2
+            };
+            self.undo();
+            chased
+        })
+    }
+
+    // the chain at i is in atari and its owner moves
+    fn escapes(&mut self, i: usize, depth: usize) -> Result<bool, GaveUp> {
+        self.nodes += 1;
+        if self.nodes > MAX_LADDER_NODES {
+            return Err(GaveUp)
+        }
+        if depth > MAX_LADDER_DEPTH {
+            return Ok(true)
+        }
+        let color = match self.stones[i] {
+            Some(color) => color,
+            None => return Ok(false),
+        };
+        // running at its liberty, or capturing a chasing chain in atari to get liberties back
+        let mut moves = self.liberties(i);
+        let chain = self.chain(i);
+        let mut seen = HashSet::new();
+        for s in chain {
+            for k in 0..self.neighbours[s].len() {
+                let n = self.neighbours[s][k];
+                if self.stones[n] == Some(color.opponent()) && !seen.contains(&n) {
+                    let other = self.chain(n);
+                    let liberties = self.liberties_of(&other);
+                    if liberties.len() == 1 {
+                        moves.extend(liberties);
+                    }
+                    seen.extend(other);
+                }
+            }
+        }
+        moves.sort_unstable();
+        moves.dedup();
+        for m in moves {
+            if self.play(m, color) {
+                let escapes = match self.liberties(i).len() {
+                    0 | 1 => Ok(false),
+                    2 => self.ladder_atari(i, depth + 1).map(|atari| atari.is_none()),
+                    _ => Ok(true),
+                };
+                self.undo();
+                if escapes? {
+                    return Ok(true)
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // the chain at i has two liberties and the opponent moves, returns the atari that captures it
+    fn ladder_atari(&mut self, i: usize, depth: usize) -> Result<Option<usize>, GaveUp> {
+        let attacker = match self.stones[i] {
+            Some(color) => color.opponent(),
+            None => return Ok(None),
+        };
+        for m in self.liberties(i) {
+            if self.play(m, attacker) {
+                let escapes = self.escapes(i, depth + 1);
+                self.undo();
+                if !escapes? {
+                    return Ok(Some(m))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // false when the point is taken or the move is a suicide, nothing is played then
+    fn play(&mut self, i: usize, color: Player) -> bool {
+        if self.stones[i].is_some() {
+            return false
+        }
+        self.stones[i] = Some(color);
+        let mut captured = Vec::new();
+        for k in 0..self.neighbours[i].len() {
+            let n = self.neighbours[i][k];
+            if self.stones[n] == Some(color.opponent()) && !captured.contains(&n) {
+                let chain = self.chain(n);
+                if self.liberties_of(&chain).is_empty() {
+                    captured.extend(chain);
+                }
+            }
+        }
+        for s in &captured {
+            self.stones[*s] = None;
+        }
+        if captured.is_empty() && self.liberties(i).is_empty() {
+            self.stones[i] = None;
+            return false
+        }
+        self.played.push((i, captured));
+        true
+    }
+
+    fn undo(&mut self) {
+        if let Some((i, captured)) = self.played.pop() {
+            let color = self.stones[i].take().expect("a played stone");
+            for s in captured {
+                self.stones[s] = Some(color.opponent());
+            }
+        }
+    }
+
+    fn chain(&mut self, i: usize) -> Vec<usize> {
+        self.generation += 1;
+        let color = self.stones[i];
+        let mut chain = vec![i];
+        self.marks[i] = self.generation;
+        let mut k = 0;
+        while k < chain.len() {
+            for &n in &self.neighbours[chain[k]] {
+                if self.stones[n] == color && self.marks[n] != self.generation {
+                    self.marks[n] = self.generation;
+                    chain.push(n);
+                }
+            }
+            k += 1;
+        }
+        chain
+    }
+
+    // sorted so the same ladder is read the same way every time
+    fn liberties_of(&self, chain: &[usize]) -> Vec<usize> {
+        let mut liberties: Vec<usize> = chain.iter()
+            .flat_map(|s| self.neighbours[*s].iter().copied())
+            .filter(|n| self.stones[*n].is_none())
+            .collect();
+        liberties.sort_unstable();
+        liberties.dedup();
+        liberties
+    }
+
+    fn liberties(&mut self, i: usize) -> Vec<usize> {
+        let chain = self.chain(i);
+        self.liberties_of(&chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::board;
+
+    fn ladder(sgf: &str) -> Option<Ladder> {
+        let board = board(sgf);
+        Tactics::read(&board).ladders.into_iter().find(|l| l.stones == vec![Point::new(4, 4)])
+    }
+
+    #[test]
+    fn ladder_to_the_edge_works() {
+        let ladder = ladder("(;SZ[9]AB[ed][de][ff]AW[ee])").unwrap();
+        assert_eq!(ladder.works, Some(true));
+        assert_eq!(ladder.start, Point::new(4, 5));
+    }
+
+    #[test]
+    fn ladder_breakers_save_the_stone() {
+        // one on each diagonal the stone can be chased along
+        let ladder = ladder("(;SZ[9]AB[ed][de][ff]AW[ee][bh][hb])").unwrap();
+        assert_eq!(ladder.works, Some(false));
+    }
+
+    #[test]
+    fn no_ladder_for_the_player_to_move() {
+        assert!(ladder("(;SZ[9]AB[ed][de][ff]AW[ee]PL[W])").is_none());
+    }
+
+    #[test]
+    fn self_atari() {
+        let board = board("(;SZ[5]AW[ba])");
+        assert!(is_self_atari(&board, Point::new(0, 0)));
+        assert!(!is_self_atari(&board, Point::new(2, 2)));
+    }
+
+    #[test]
+    fn suicide_is_not_self_atari() {
+        let board = board("(;SZ[5]AW[ba][ab])");
+        assert!(!is_self_atari(&board, Point::new(0, 0)));
+    }
+}
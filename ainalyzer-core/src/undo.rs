@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use sgf_parser::{Outcome, RuleSet};
+
+use crate::Player;
+use crate::board::{self, Board};
+use crate::history::{History, NodeIndex, Subtree};
+use crate::markup::Markup;
+use crate::rules::Rules;
+
+// a long review keeps this many edits, the oldest are forgotten
+const MAX_UNDO: usize = 200;
+
+// a node's children in their order
+type Children = Vec<NodeIndex<u32>>;
+
+// An edit with what it changed on both sides, undone and redone in place on the record
+#[derive(Debug, Clone)]
+pub enum Edit {
+    // a move, a pass or a setup node
+    AddedNode(Subtree),
+    RemovedSubtree(Subtree),
+    // the stones of an existing setup node, or of the root, and the player it gives the turn to
+    Setup { node: NodeIndex<u32>, before: (Vec<board::Setup>, Player), after: (Vec<board::Setup>, Player) },
+    Markup { node: NodeIndex<u32>, before: Vec<Markup>, after: Vec<Markup> },
+    Comment { node: NodeIndex<u32>, before: String, after: String },
+    // the game's result and the territory on the scored node
+    Score { node: NodeIndex<u32>, before: (Option<Outcome>, Vec<(Player, usize)>), after: (Option<Outcome>, Vec<(Player, usize)>) },
+    Rules { before: (Rules, Option<RuleSet>), after: (Rules, Option<RuleSet>) },
+    // each reordered parent with its children before and after
+    Order(Vec<(NodeIndex<u32>, Children, Children)>),
+}
+
+impl Edit {
+    // the current node, just added
+    pub fn added(history: &History) -> Edit {
+        Edit::AddedNode(history.subtree(history.current_index))
+    }
+
+    pub fn setup_state(history: &History, node: NodeIndex<u32>) -> (Vec<board::Setup>, Player) {
+        let mov = &history.moves[node];
+        (mov.setup.clone(), mov.player)
+    }
+
+    // a board edit changes the node it was made on, or adds a setup node after it
+    pub fn setup(history: &History, node: NodeIndex<u32>, before: (Vec<board::Setup>, Player)) -> Edit {
+        if history.current_index != node {
+            return Edit::added(history)
+        }
+        Edit::Setup { node, before, after: Edit::setup_state(history, node) }
+    }
+
+    // the children of every node of the current line, reordering only moves those
+    pub fn line_order(history: &History) -> Vec<(NodeIndex<u32>, Children)> {
+        history.path_to_current().into_iter()
+            .map(|i| (i, history.children(i)))
+            .collect()
+    }
+
+    pub fn order(before: Vec<(NodeIndex<u32>, Children)>, history: &History) -> Edit {
+        Edit::Order(before.into_iter()
+            .map(|(parent, before)| (parent, before, history.children(parent)))
+            .filter(|(_, before, after)| before != after)
+            .collect())
+    }
+
+    // a position changed in place, the engine can't get there by moving along the tree
+    pub fn is_setup(&self) -> bool {
+        matches!(self, Edit::Setup { .. })
+    }
+
+    // the board is left on the edited node, as the edit found it or as it left it,
+    // returns the nodes put back under another index
+    fn apply(&self, board: &mut Board, forward: bool) -> HashMap<NodeIndex<u32>, NodeIndex<u32>> {
+        match self {
+            Edit::AddedNode(subtree) | Edit::RemovedSubtree(subtree) => {
+                if forward == matches!(self, Edit::AddedNode(_)) {
+                    board.go_to(subtree.parent);
+                    let renamed = board.history.restore_subtree(subtree);
+                    board.go_to(*renamed.get(&subtree.root()).unwrap_or(&subtree.root()));
+                    return renamed
+                } else {
+                    board.go_to(subtree.root());
+                    board.delete_variation();
+                }
+            },
+            Edit::Setup { node, before, after } => {
+                board.go_to(*node);
+                let (setup, player) = pick(forward, before, after).clone();
+                board.replace_setup(setup, player);
+            },
+            Edit::Markup { node, before, after } => {
+                board.go_to(*node);
+                board.history.moves[*node].markup = pick(forward, before, after).clone();
+            },
+            Edit::Comment { node, before, after } => {
+                board.go_to(*node);
+                board.history.moves[*node].comment = pick(forward, before, after).clone();
+            },
+            Edit::Score { node, before, after } => {
+                board.go_to(*node);
+                let (result, territory) = pick(forward, before, after).clone();
+                board.history.set_result(result);
+                board.history.moves[*node].territory = territory;
+            },
+            Edit::Rules { before, after } => {
+                let (rules, rule_set) = pick(forward, before, after).clone();
+                board.history.replace_rules(rules, rule_set);
+            },
+            Edit::Order(orders) => {
+                for (parent, before, after) in orders {
+                    board.history.set_children(*parent, pick(forward, before, after));
+                }
+            },
+        }
+        HashMap::new()
+    }
+
+    fn rename(&mut self, renamed: &HashMap<NodeIndex<u32>, NodeIndex<u32>>) {
+        let rename = |i: &mut NodeIndex<u32>| if let Some(new) = renamed.get(i) {
+            *i = *new;
+        };
+        match self {
+            Edit::AddedNode(subtree) | Edit::RemovedSubtree(subtree) => subtree.rename(renamed),
+            Edit::Setup { node, .. } | Edit::Markup { node, .. } | Edit::Comment { node, .. } | Edit::Score { node, .. } => rename(node),
+            Edit::Rules { .. } => (),
+            Edit::Order(orders) => {
+                for (parent, before, after) in orders {
+                    rename(parent);
+                    before.iter_mut().for_each(rename);
+                    after.iter_mut().for_each(rename);
+                }
+            },
+        }
+    }
+}
+
+fn pick<'a, T: ?Sized>(forward: bool, before: &'a T, after: &'a T) -> &'a T {
+    if forward { after } else { before }
+}
+
+// Edits are logged as they are made, walking through the moves is never
+// recorded so it can't be undone by mistake
+#[derive(Debug, Clone)]
+pub struct Undo {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    saved: Option<usize>, // undo depth of the last save, None once it can't be reached
+}
+
+impl Default for Undo {
+    // a fresh or just opened game is saved
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            saved: Some(0),
+        }
+    }
+}
+
+impl Undo {
+    pub fn record(&mut self, edit: Edit) {
+        if self.saved.is_some_and(|depth| depth > self.undo.len()) {
+            self.saved = None;
+        }
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+            self.saved = self.saved.and_then(|depth| depth.checked_sub(1));
+        }
+    }
+
+    // a typing session is undone at once, its later changes only move the last comment edit forward
+    pub fn amend(&mut self, edit: Edit) {
+        match (self.undo.last_mut(), edit) {
+            (Some(Edit::Comment { node, after, .. }), Edit::Comment { node: edited, after: comment, .. }) if *node == edited => {
+                *after = comment;
+                if self.saved == Some(self.undo.len()) {
+                    self.saved = None;
+                }
+            },
+            (_, edit) => self.record(edit),
+        }
+    }
+
+    pub fn undo(&mut self, board: &mut Board) -> Option<&Edit> {
+        let edit = self.undo.pop()?;
+        let renamed = edit.apply(board, false);
+        self.redo.push(edit);
+        self.rename(&renamed);
+        self.redo.last()
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> Option<&Edit> {
+        let edit = self.redo.pop()?;
+        let renamed = edit.apply(board, true);
+        self.undo.push(edit);
+        self.rename(&renamed);
+        self.undo.last()
+    }
+
+    // a node put back under another index is followed by every edit made on it
+    fn rename(&mut self, renamed: &HashMap<NodeIndex<u32>, NodeIndex<u32>>) {
+        if renamed.is_empty() {
+            return
+        }
+        for edit in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            edit.rename(renamed);
+        }
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.undo.len());
+    }
+
+    // the file holds edits made outside of this log, like in another game of a collection
+    pub fn mark_unsaved(&mut self) {
+        self.saved = None;
+    }
+
+    pub fn is_saved(&self) -> bool {
+        self.saved == Some(self.undo.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Point, Stone};
+    use crate::board::tests::board;
+
+    fn sgf(board: &Board) -> String {
+        (&board.history.into_game_tree()).into()
+    }
+
+    #[test]
+    fn deletion_is_undone_after_its_indexes_were_taken() {
+        let mut board = board("(;SZ[9];B[aa](;W[bb];B[cc])(;W[dd]))");
+        let mut undo = Undo::default();
+        let node = board.history.current_index;
+        board.history.set_comment(String::from("note"));
+        undo.record(Edit::Comment { node, before: String::new(), after: String::from("note") });
+        board.previous_state();
+        let variation = board.history.current_index;
+        undo.record(Edit::RemovedSubtree(board.delete_variation().unwrap()));
+
+        // a move nobody logged takes a freed index
+        board.play(Point::new(5, 5), Stone::white());
+        assert!(board.history.moves.contains_node(variation) || board.history.moves.contains_node(node));
+        board.previous_state();
+
+        undo.undo(&mut board);
+        assert!(sgf(&board).contains("(;W[bb];B[cc]C[note])"), "{}", sgf(&board));
+        assert_eq!(board.history.moves[board.history.current_index].index, Some(Board::coord_to_idx(Point::new(1, 1), 9)));
+        // the picker leads into the variation again
+        board.previous_state();
+        board.next_state();
+        assert_eq!(board.history.moves[board.history.current_index].index, Some(Board::coord_to_idx(Point::new(1, 1), 9)));
+
+        // the comment was made on a node now under another index
+        undo.undo(&mut board);
+        assert!(sgf(&board).contains("(;W[bb];B[cc])"), "{}", sgf(&board));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::Player;
+use crate::board::Point;
+
+// sgf allows boards up to 52x52, keys are indexed by coordinates so every board size shares the table
+const MAX_BOARD_SIZE: usize = 52;
+
+lazy_static! {
+    static ref KEYS: Vec<[u64; 2]> = {
+        // fixed seed so position hashes are stable between runs
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..MAX_BOARD_SIZE*MAX_BOARD_SIZE).map(|_| [splitmix64(&mut state), splitmix64(&mut state)]).collect()
+    };
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// xor it in to add a stone to the hash, xor it again to remove it
+pub fn key(p: Point, color: Player) -> u64 {
+    KEYS[p.x as usize * MAX_BOARD_SIZE + p.y as usize][match color {
+        Player::Black => 0,
+        Player::White => 1,
+    }]
+}
@@ -14,7 +14,7 @@ pub struct Engine {
 impl Engine {
     pub fn new() -> Self {
         let mut controller = libgtp::Controller::new("./KataGo/katago", &["gtp", "-model", "./KataGo/model.bin.gz", "-config", "./KataGo/default_gtp.cfg"]);
-        controller.send_command(COMMAND_RULES_JAPANESE.clone()).unwrap();
+        controller.send_command(command_rules(&ainalyzer_core::rules::Rules::default())).unwrap();
         controller.send_command(COMMAND_KOMI.clone()).unwrap();
         controller.send_command(COMMAND_CLEARBOARD.clone()).unwrap();
         Self {
@@ -26,12 +26,17 @@ impl Engine {
     #[allow(dead_code)]
     pub fn restart_engine(&mut self) {
         let mut controller = libgtp::Controller::new("./KataGo/katago", &["gtp", "-model", "./KataGo/model.bin.gz", "-config", "./KataGo/default_gtp.cfg"]);
-        controller.send_command(COMMAND_RULES_JAPANESE.clone()).unwrap();
+        controller.send_command(command_rules(&ainalyzer_core::rules::Rules::default())).unwrap();
         controller.send_command(COMMAND_KOMI.clone()).unwrap();
         controller.send_command(COMMAND_CLEARBOARD.clone()).unwrap();
         self.controller = Arc::new(Mutex::new(controller));
     }
 
+    pub fn set_rules(&mut self, rules: &ainalyzer_core::rules::Rules) {
+        let mut engine = self.controller.lock().expect("could not get engine");
+        engine.send_command(command_rules(rules)).unwrap();
+    }
+
     pub fn view<'a>(&'a self) -> Element<'a, crate::Message> {
         let analyze = button("Start analyze")
             .on_press(crate::Message::StartAnalyze);
@@ -45,13 +50,9 @@ impl Engine {
             .into()
     }
 
-    pub fn start_analyze(&self) -> libgtp::Answer {
+    pub fn start_analyze(&self, turn: ainalyzer_core::Player) -> libgtp::Answer {
         let mut engine = self.controller.lock().expect("could not get engine");
-        match self.ownership {
-            true => engine.send_command(COMMAND_ANALYZE_OWNERSHIP.clone()).unwrap(),
-            false => engine.send_command(COMMAND_ANALYZE.clone()).unwrap(),
-
-        }
+        engine.send_command(command_analyze(turn, self.ownership)).unwrap()
     }
     
     pub fn stop_analyze(&self) -> libgtp::Answer {
@@ -70,12 +71,41 @@ impl Engine {
         }
     }
 
-    pub fn play(&mut self, turn: crate::Player, p: crate::goban::Point, winrate: Option<(u64, f32)>) -> Result<libgtp::Answer, std::io::Error> {
+    pub fn play(&mut self, turn: ainalyzer_core::Player, p: Option<ainalyzer_core::board::Point>, winrate: Option<(u64, f32)>) -> Result<libgtp::Answer, std::io::Error> {
         let mut engine = self.controller.lock().expect("could not get engine");
-        let answer = engine.send_command(format!("play {} {}", turn, p).as_str().parse().unwrap())?;
+        let vertex = match p {
+            Some(p) => p.to_string(),
+            None => "pass".to_string(),
+        };
+        let answer = engine.send_command(format!("play {} {}", turn, vertex).as_str().parse().unwrap())?;
         Ok(answer)
     }
 
+    pub fn set_board_size(&mut self, board_size: u32) {
+        let mut engine = self.controller.lock().expect("could not get engine");
+        engine.send_command(format!("boardsize {}", board_size).as_str().parse().unwrap()).unwrap();
+        engine.send_command(COMMAND_CLEARBOARD.clone()).unwrap();
+    }
+
+    pub fn clear_board(&mut self) {
+        let mut engine = self.controller.lock().expect("could not get engine");
+        engine.send_command(COMMAND_CLEARBOARD.clone()).unwrap();
+    }
+
+    // replaces the whole board, the engine's move history is lost
+    pub fn set_position(&mut self, stones: &[(ainalyzer_core::Player, ainalyzer_core::board::Point)]) {
+        let mut engine = self.controller.lock().expect("could not get engine");
+        let position = stones.iter().map(|(color, p)| format!(" {} {}", color, p)).collect::<String>();
+        engine.send_command(format!("set_position{}", position).as_str().parse().unwrap()).unwrap();
+    }
+
+    // on a cleared board, the engine gives the turn to white
+    pub fn set_free_handicap(&mut self, stones: &[ainalyzer_core::board::Point]) {
+        let mut engine = self.controller.lock().expect("could not get engine");
+        let vertices = stones.iter().map(|p| format!(" {}", p)).collect::<String>();
+        engine.send_command(format!("set_free_handicap{}", vertices).as_str().parse().unwrap()).unwrap();
+    }
+
     pub fn undo(&mut self) {
         let mut engine = self.controller.lock().expect("could not get engine");
         engine.send_command(COMMAND_UNDO.clone()).unwrap();
@@ -0,0 +1,142 @@
+use crate::{GobanEvent, Message};
+use ainalyzer_core::Player;
+use ainalyzer_core::board::Move;
+use ainalyzer_core::history::{History, NodeIndex};
+use iced::{widget::canvas, Element, Rectangle};
+use iced::widget::canvas::Path;
+
+// size of a node's cell
+const SPACING: f32 = 24.0;
+
+// A node placed in the tree view, one column per move and one row per branch
+struct TreeNode<'a> {
+    index: NodeIndex<u32>,
+    mov: &'a Move,
+    column: usize,
+    row: usize,
+    parent: Option<(usize, usize)>, // column and row
+}
+
+// Every variation of the review, clicking a node jumps the goban there
+pub struct GameTree<'a> {
+    history: &'a History,
+    nodes: Vec<TreeNode<'a>>,
+    columns: usize,
+    rows: usize,
+}
+
+impl<'a> GameTree<'a> {
+    // the main line runs on the first row, each other branch opens a row below the ones already drawn
+    pub fn new(history: &'a History) -> Self {
+        let mut nodes = Vec::new();
+        let mut rows = 1;
+        // the row is None for a branch that still needs its own
+        let mut stack = vec![(NodeIndex::new(0), 0, Some(0), None)];
+        while let Some((index, column, row, parent)) = stack.pop() {
+            let row = row.unwrap_or_else(|| {
+                rows += 1;
+                rows - 1
+            });
+            nodes.push(TreeNode { index, mov: &history.moves[index], column, row, parent });
+            // the first child is laid out first, its whole line before its siblings
+            for (i, child) in history.children(index).into_iter().enumerate().rev() {
+                stack.push((child, column + 1, if i == 0 { Some(row) } else { None }, Some((column, row))));
+            }
+        }
+        let columns = nodes.iter().map(|n| n.column + 1).max().unwrap_or(1);
+        Self { history, nodes, columns, rows }
+    }
+
+    pub fn view(self) -> Element<'a, Message> {
+        let (width, height) = (self.columns as f32 * SPACING, self.rows as f32 * SPACING);
+        let tree = canvas(self)
+            .width(iced::Length::Units(width as u16))
+            .height(iced::Length::Units(height as u16));
+        iced::widget::scrollable(tree)
+            .horizontal_scroll(iced::widget::scrollable::Properties::default())
+            .height(iced::Length::FillPortion(3))
+            .into()
+    }
+
+    fn center(column: usize, row: usize) -> iced::Point {
+        iced::Point { x: (column as f32 + 0.5) * SPACING, y: (row as f32 + 0.5) * SPACING }
+    }
+}
+
+impl<'a> canvas::Program<Message> for GameTree<'a> {
+    type State = ();
+
+    fn draw(
+            &self,
+            _state: &(),
+            _theme: &iced::theme::Theme,
+            bounds: Rectangle,
+            _cursor_position: canvas::Cursor,
+        ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(bounds.size());
+        let line = canvas::Stroke::default()
+            .with_color(iced::Color::from_rgb(0.6, 0.6, 0.6))
+            .with_width(2.0);
+        // a branch leaves its parent diagonally then runs along its row
+        for node in &self.nodes {
+            if let Some((column, row)) = node.parent {
+                let (from, to) = (GameTree::center(column, row), GameTree::center(node.column, node.row));
+                let path = Path::new(|b| {
+                    b.move_to(from);
+                    if row != node.row {
+                        b.line_to(iced::Point { x: from.x, y: to.y - SPACING / 2.0 });
+                    }
+                    b.line_to(to);
+                });
+                frame.stroke(&path, line.clone());
+            }
+        }
+        for node in &self.nodes {
+            let center = GameTree::center(node.column, node.row);
+            let mov = node.mov;
+            let radius = SPACING / 3.0;
+            if node.parent.is_none() || mov.is_setup() {
+                // the root and setup nodes hold stones without a move
+                let square = Path::rectangle(iced::Point { x: center.x - radius, y: center.y - radius }, iced::Size { width: radius * 2.0, height: radius * 2.0 });
+                frame.fill(&square, iced::Color::from_rgb(0.5, 0.5, 0.5));
+            } else {
+                let stone = Path::circle(center, radius);
+                frame.fill(&stone, match mov.player {
+                    Player::Black => iced::Color::BLACK,
+                    Player::White => iced::Color::WHITE,
+                });
+                frame.stroke(&stone, canvas::Stroke::default().with_color(iced::Color::from_rgb(0.6, 0.6, 0.6)).with_width(1.0));
+                // a pass leaves an empty stone
+                if mov.is_pass() {
+                    frame.fill(&Path::circle(center, radius / 2.0), iced::Color::from_rgb(0.5, 0.5, 0.5));
+                }
+            }
+            if !mov.comment.is_empty() {
+                frame.fill(&Path::circle(iced::Point { x: center.x + radius, y: center.y - radius }, SPACING / 10.0), iced::Color::from_rgb(0.2, 0.5, 1.0));
+            }
+            if node.index == self.history.current_index {
+                let ring = canvas::Stroke::default()
+                    .with_color(iced::Color::from_rgb(1.0, 0.0, 0.0))
+                    .with_width(3.0);
+                frame.stroke(&Path::circle(center, SPACING / 2.2), ring);
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn update(
+            &self,
+            _state: &mut (),
+            event: canvas::Event,
+            bounds: Rectangle,
+            cursor: canvas::Cursor,
+        ) -> (canvas::event::Status, Option<Message>) {
+        if let (canvas::Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)), Some(position)) = (event, cursor.position_in(&bounds)) {
+            let (column, row) = ((position.x / SPACING) as usize, (position.y / SPACING) as usize);
+            if let Some(node) = self.nodes.iter().find(|n| n.column == column && n.row == row) {
+                return (canvas::event::Status::Captured, Some(Message::Goban(GobanEvent::GoTo(node.index))))
+            }
+        }
+        (canvas::event::Status::Ignored, None)
+    }
+}
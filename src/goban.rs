@@ -1,84 +1,153 @@
-use crate::Player;
-use crate::history::*;
 use crate::Message;
 use crate::GobanEvent;
-use std::collections::HashSet;
+use ainalyzer_core::Player;
+use ainalyzer_core::board::{Board, Point, Stone};
+use ainalyzer_core::history::{History, NodeIndex};
+use ainalyzer_core::influence::Influence;
+use ainalyzer_core::life;
+use ainalyzer_core::markup::{Markup, Shape};
+use ainalyzer_core::scoring::Score;
+use ainalyzer_core::tactics::{self, Tactics};
+use ainalyzer_core::undo::{Edit, Undo};
+use std::collections::{HashMap, HashSet};
 use libgtp::model::Info;
 use iced::{widget::canvas, Rectangle, Element};
 use iced::widget::canvas::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Point {
-    pub x: u32,
-    pub y: u32,
+// How the board is turned on screen, the board itself never changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    pub quarter_turns: u32, // clockwise
+    pub mirror: bool, // left and right swapped, done before turning
 }
 
-impl std::fmt::Display for Point {
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        let x = if self.x > 8 {
-            self.x + 1
-        } else {
-            self.x
-        };
+impl Orientation {
+    pub fn rotate(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
 
-        write!(f, "{}{}", ((x as u8 + 65) as char).to_uppercase(), self.y+1)
+    pub fn flip(&mut self) {
+        self.mirror = !self.mirror;
     }
-}
 
-impl Point {
-    pub fn new(x: u32, y: u32) -> Self {
-        Self {
-            x,
-            y,
+    // white sits on the other side of the board
+    pub fn toggle_white_side(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 2) % 4;
+    }
+
+    pub fn to_screen(&self, p: Point, board_size: u32) -> Point {
+        let last = board_size - 1;
+        let (mut x, mut y) = (p.x, p.y);
+        if self.mirror {
+            x = last - x;
+        }
+        for _ in 0..self.quarter_turns {
+            (x, y) = (last - y, x);
         }
+        Point::new(x, y)
     }
 
-    pub fn as_coord_tuple(&self) -> (u8, u8) {
-        (self.x as u8, self.y as u8)
+    pub fn to_board(&self, p: Point, board_size: u32) -> Point {
+        let last = board_size - 1;
+        let (mut x, mut y) = (p.x, p.y);
+        for _ in 0..self.quarter_turns {
+            (x, y) = (y, last - x);
+        }
+        if self.mirror {
+            x = last - x;
+        }
+        Point::new(x, y)
+    }
+
+    // the engine gives 1-based coordinates
+    fn to_screen_tuple(&self, (x, y): (u8, u8), board_size: u32) -> (u8, u8) {
+        let p = self.to_screen(Point::new(x as u32 - 1, y as u32 - 1), board_size);
+        (p.x as u8 + 1, p.y as u8 + 1)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Stone {
-    pub visible: bool,
-    pub color: Player,
+// Which stones show the number of the move that played them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveNumbers {
+    Off,
+    All,
+    Last(u32),
+    // the moves of the current variation after it left its parent line
+    SinceBranch,
 }
 
-impl Default for Stone {
-    fn default() -> Self {
-        Self {
-            visible: false,
-            color: Player::Black,
-        }
+impl MoveNumbers {
+    pub const ALL: [MoveNumbers; 6] = [MoveNumbers::Off, MoveNumbers::All, MoveNumbers::Last(5), MoveNumbers::Last(10), MoveNumbers::Last(20), MoveNumbers::SinceBranch];
+
+    pub fn next(self) -> Self {
+        let i = MoveNumbers::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        MoveNumbers::ALL[(i + 1) % MoveNumbers::ALL.len()]
     }
 }
 
-impl Stone {
-    pub fn new(color: Player) -> Self {
-        Self {
-            visible: true,
-            color
+impl std::fmt::Display for MoveNumbers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "No move numbers"),
+            Self::All => write!(f, "All move numbers"),
+            Self::Last(n) => write!(f, "Last {} moves", n),
+            Self::SinceBranch => write!(f, "Moves since the branch"),
         }
     }
-    pub fn black() -> Self {
-        Self {
-            visible: true,
-            color: Player::Black,
-        }
+}
+
+// What a click puts on the board in markup mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupTool {
+    Shape(Shape),
+    Label,
+    // arrows and lines take a click on each end
+    Arrow,
+    Line,
+}
+
+impl MarkupTool {
+    pub const ALL: [MarkupTool; 7] = [MarkupTool::Shape(Shape::Triangle), MarkupTool::Shape(Shape::Square), MarkupTool::Shape(Shape::Circle), MarkupTool::Shape(Shape::Cross), MarkupTool::Label, MarkupTool::Arrow, MarkupTool::Line];
+
+    fn two_points(&self) -> bool {
+        matches!(self, MarkupTool::Arrow | MarkupTool::Line)
     }
+}
 
-    pub fn white() -> Self {
-        Self {
-            visible: true,
-            color: Player::White,
+impl std::fmt::Display for MarkupTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shape(Shape::Triangle) => write!(f, "Triangle"),
+            Self::Shape(Shape::Square) => write!(f, "Square"),
+            Self::Shape(Shape::Circle) => write!(f, "Circle"),
+            Self::Shape(Shape::Cross) => write!(f, "Cross"),
+            Self::Label => write!(f, "Label"),
+            Self::Arrow => write!(f, "Arrow"),
+            Self::Line => write!(f, "Line"),
         }
     }
+}
+
+const BOARD_COLOR: iced::Color = iced::Color { r: 219.0 / 255.0, g: 185.0 / 255.0, b: 52.0 / 255.0, a: 1.0 };
+
+fn player_color(player: Player) -> iced::Color {
+    match player {
+        Player::Black => iced::Color::BLACK,
+        Player::White => iced::Color::WHITE,
+    }
+}
 
+// the core crate knows nothing about drawing, stones get their shapes here
+trait StoneView {
+    fn hover(&self, rect: &Rectangle, coord: &Point, board_size: u32) -> (Path, iced::Color);
+    fn possibilty(&self, rect: &Rectangle, coord: Point, board_size: u32) -> (Path, iced::Color);
+}
 
-    fn hover(&self, rect: &Rectangle, coord: &Point) -> (Path, iced::Color) {
+impl StoneView for Stone {
+    fn hover(&self, rect: &Rectangle, coord: &Point, board_size: u32) -> (Path, iced::Color) {
         let size = rect.height;
-        let size_stone = size/20.0;
-        let radius = size/41.8;
+        let size_stone = size/(board_size + 1) as f32;
+        let radius = size_stone/2.09;
         let color = match self.color {
             Player::Black => iced::Color::new(0.0, 0.0, 0.0, 0.7),
             Player::White => iced::Color::new(1.0, 1.0, 1.0, 0.7),
@@ -87,10 +156,10 @@ impl Stone {
          color.into())
     }
 
-    fn possibilty(&self, rect: &Rectangle, coord: Point) -> (Path, iced::Color) {
+    fn possibilty(&self, rect: &Rectangle, coord: Point, board_size: u32) -> (Path, iced::Color) {
         let size = rect.height;
-        let size_stone = size/20.0;
-        let radius = size/70.0;
+        let size_stone = size/(board_size + 1) as f32;
+        let radius = size_stone/3.5;
         let color = match self.color {
             Player::Black => iced::Color::new(0.0, 0.0, 0.0, 0.7),
             Player::White => iced::Color::new(1.0, 1.0, 1.0, 0.7),
@@ -101,6 +170,19 @@ impl Stone {
     }
 }
 
+// shades each point from black to white, the ownership is given from black's point of view
+fn draw_ownership(frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32, ownership: impl Iterator<Item = (Point, f32)>) {
+    for (point, ownership) in ownership {
+        let mut color = if ownership.is_sign_positive() {
+            iced::Color::BLACK
+        } else {
+            iced::Color::WHITE
+        };
+        color.a = ownership.abs() * 0.7;
+        frame.fill(&Path::rectangle(iced::Point { x: rect.x + (point.x+1) as f32 * spacing - spacing/2.0, y: rect.y + (point.y+1) as f32 * spacing - spacing/2.0}, iced::Size { width: spacing, height: spacing }), color);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AnalyzeInfo(pub Info);
 
@@ -113,6 +195,12 @@ impl AnalyzeInfo {
             .max_by_key(|x| (x * 1000.0) as u64).unwrap()
     }
 
+    // from black's point of view, ownership is given for the player to move
+    fn black_ownership_at(&self, turn: Player, p: Point, board_size: u32) -> Option<f32> {
+        let i = ((board_size - 1 - p.y) * board_size + p.x) as usize;
+        self.0.ownership.get(i).map(|o| if turn == Player::Black { *o } else { -o })
+    }
+
     fn winrate_of(&self, turn: Player, p: Point) -> Option<f32> {
         if let Some(mov) = self.0.explored_moves.iter()
                             .find(|x| x.coord.to_tuple().is_some() && x.coord.to_tuple().unwrap() == p.as_coord_tuple()) {
@@ -122,35 +210,25 @@ impl AnalyzeInfo {
         }
     }
 
-    fn draw(&self, frame: &mut canvas::Frame, rect: &Rectangle, size: f32, player: Player) {
-        self.0.ownership.iter().enumerate().for_each(|(point, ownership)| {
-            let mut color = if ownership.is_sign_positive() {
-                match player {
-                    Player::Black => iced::Color::BLACK,
-                    Player::White => iced::Color::WHITE,
-                }
-            } else {
-                match player {
-                    Player::Black => iced::Color::WHITE,
-                    Player::White => iced::Color::BLACK,
-                }
-            };
-            color.a = ownership.abs() * 0.7;
-            let point = Point::new(point as u32 % 19, (360 - point as u32) / 19);
-            frame.fill(&Path::rectangle(iced::Point { x: rect.x + (point.x+1) as f32 * size/20.0 - size/40.0, y: rect.y + (point.y+1) as f32 * size/20.0 - size/40.0}, iced::Size { width: size/20.0, height: size/20.0 }), color);
-        });
+    fn draw(&self, frame: &mut canvas::Frame, rect: &Rectangle, size: f32, player: Player, board_size: u32, orientation: Orientation) {
+        let spacing = size/(board_size + 1) as f32;
+        let points = board_size * board_size;
+        draw_ownership(frame, rect, spacing, self.0.ownership.iter().enumerate().map(|(point, ownership)| {
+            let point = Point::new(point as u32 % board_size, (points - 1 - point as u32) / board_size);
+            (orientation.to_screen(point, board_size), if player == Player::Black { *ownership } else { -ownership })
+        }));
         let max_winrate = self.0.explored_moves.iter()
             .filter(|x| x.coord.to_tuple().is_some())
-            .map(|x| ((x.winrate * 1000.0) as u64, (x.coord.to_tuple().unwrap())))
+            .map(|x| ((x.winrate * 1000.0) as u64, orientation.to_screen_tuple(x.coord.to_tuple().unwrap(), board_size)))
             .max_by_key(|x| x.0).unwrap();
         let max_visits = self.0.explored_moves.iter()
             .filter(|x| x.coord.to_tuple().is_some())
-            .map(|x| (x.visits, (x.coord.to_tuple().unwrap())))
+            .map(|x| (x.visits, orientation.to_screen_tuple(x.coord.to_tuple().unwrap(), board_size)))
             .max_by_key(|x| x.0).unwrap();
 
         let gradiant_pos = canvas::gradient::Position::Relative {
-            top_left: iced::Point { x: rect.x + (max_winrate.1.0) as f32 * size/20.0 - size/40.0, y: rect.y + (max_winrate.1.1) as f32 * size/20.0 - size/40.0 },
-            size: iced::Size { width: size/20.0, height: size/20.0 },
+            top_left: iced::Point { x: rect.x + (max_winrate.1.0) as f32 * spacing - spacing/2.0, y: rect.y + (max_winrate.1.1) as f32 * spacing - spacing/2.0 },
+            size: iced::Size { width: spacing, height: spacing },
             start: canvas::gradient::Location::TopLeft,
             end: canvas::gradient::Location::BottomRight,
         };
@@ -159,11 +237,11 @@ impl AnalyzeInfo {
             .add_stop(0.5, iced::Color::from_rgba(0.0, 1.0, 0.0, 0.6))
             .add_stop(1.0, iced::color!(0, 0, 0, 0.0))
             .build().expect("failed to create gradiant");
-        frame.fill(&Path::circle(iced::Point { x: rect.x + (max_winrate.1.0) as f32 * size/20.0, y: rect.y + (max_winrate.1.1) as f32 * size/20.0 }, size/40.0), gradiant);
+        frame.fill(&Path::circle(iced::Point { x: rect.x + (max_winrate.1.0) as f32 * spacing, y: rect.y + (max_winrate.1.1) as f32 * spacing }, spacing/2.0), gradiant);
 
         let gradiant_pos = canvas::gradient::Position::Relative {
-            top_left: iced::Point { x: rect.x + (max_visits.1.0) as f32 * size/20.0 - size/40.0, y: rect.y + (max_visits.1.1) as f32 * size/20.0 - size/40.0 },
-            size: iced::Size { width: size/20.0, height: size/20.0 },
+            top_left: iced::Point { x: rect.x + (max_visits.1.0) as f32 * spacing - spacing/2.0, y: rect.y + (max_visits.1.1) as f32 * spacing - spacing/2.0 },
+            size: iced::Size { width: spacing, height: spacing },
             start: canvas::gradient::Location::TopRight,
             end: canvas::gradient::Location::BottomLeft,
         };
@@ -172,15 +250,15 @@ impl AnalyzeInfo {
             .add_stop(0.5, iced::color!(7, 109, 252, 0.6))
             .add_stop(1.0, iced::color!(0, 0, 0, 0.0))
             .build().expect("failed to create gradiant");
-        frame.fill(&Path::circle(iced::Point { x: rect.x + (max_visits.1.0) as f32 * size/20.0, y: rect.y + (max_visits.1.1) as f32 * size/20.0 }, size/40.0), gradiant);
+        frame.fill(&Path::circle(iced::Point { x: rect.x + (max_visits.1.0) as f32 * spacing, y: rect.y + (max_visits.1.1) as f32 * spacing }, spacing/2.0), gradiant);
 
         self.0.explored_moves.iter().for_each(|move_info| {
-            let point = move_info.coord.to_tuple();
+            let point = move_info.coord.to_tuple().map(|t| orientation.to_screen_tuple(t, board_size));
             if let Some((x, y)) = point {
                 if ((x, y) != max_winrate.1) && ((x, y) != max_visits.1) {
                     let gradiant_pos = canvas::gradient::Position::Relative {
-                        top_left: iced::Point { x: rect.x + (x) as f32 * size/20.0 - size/40.0, y: rect.y + (y) as f32 * size/20.0 - size/40.0 },
-                        size: iced::Size { width: size/20.0, height: size/20.0 },
+                        top_left: iced::Point { x: rect.x + (x) as f32 * spacing - spacing/2.0, y: rect.y + (y) as f32 * spacing - spacing/2.0 },
+                        size: iced::Size { width: spacing, height: spacing },
                         start: canvas::gradient::Location::TopRight,
                         end: canvas::gradient::Location::BottomLeft,
                     };
@@ -189,66 +267,98 @@ impl AnalyzeInfo {
                         .add_stop(0.5, iced::color!(255, 0, 0, 0.6))
                         .add_stop(1.0, iced::color!(0, 0, 0, 0.0))
                         .build().expect("failed to create gradiant");
-                    frame.fill(&Path::circle(iced::Point { x: rect.x + (x) as f32 * size/20.0, y: rect.y + (y) as f32 * size/20.0 }, size/40.0), gradiant);
+                    frame.fill(&Path::circle(iced::Point { x: rect.x + (x) as f32 * spacing, y: rect.y + (y) as f32 * spacing }, spacing/2.0), gradiant);
                 }
 
                 let mut text = canvas::Text::default();
                 text.content = format!("{:^4.1}%", move_info.winrate * 100.0);
-                text.size = size/69.0;
+                text.size = spacing/3.45;
                 text.horizontal_alignment = iced::alignment::Horizontal::Center;
-                text.position = iced::Point { x: rect.x + (x as f32) * size/20.0, y: rect.y + (y as f32 - 0.32) * size/20.0 };
+                text.position = iced::Point { x: rect.x + (x as f32) * spacing, y: rect.y + (y as f32 - 0.32) * spacing };
                 frame.fill_text(text);
                 let mut text = canvas::Text::default();
                 text.content = format_num::format_num!("^.2s", move_info.visits as f64);
-                text.size = size/69.0;
+                text.size = spacing/3.45;
                 text.horizontal_alignment = iced::alignment::Horizontal::Center;
-                text.position = iced::Point { x: rect.x + (x as f32) * size/20.0, y: rect.y + (y as f32) * size/20.0 };
+                text.position = iced::Point { x: rect.x + (x as f32) * spacing, y: rect.y + (y as f32) * spacing };
                 frame.fill_text(text);
             }
         });
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Group {
-    stones: Vec<Point>,
-    liberties: u64,
-    team: Player,
-}
-
 #[derive(Debug, Default, Clone)]
 pub struct GobanState {
     pub hover: Option<Point>,
+    pub self_atari: bool, // the hovered move leaves its own chain in atari
+    pub markup_start: Option<Point>, // first end of an arrow or a line
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GobanMode {
+    Play,
+    // clicking a group toggles it dead or alive for scoring
+    MarkDead,
+    // left and right clicks set black and white stones, clicking a stone clears it
+    Edit,
+    // clicks add or remove marks on the current node
+    Markup(MarkupTool),
 }
 
+// a reading with the position, the node and the player to move it was made for
+type TacticsCache = Option<((u64, NodeIndex<u32>, Player), Tactics)>;
+
 #[derive(Debug, Clone)]
 pub struct Goban {
-    pub stones: Vec<Stone>, //The points coord are used as the goban coords for the stones to be placed
-    pub last_move: Option<Point>,
-    pub ko: Option<Point>,
-    pub current_move_number: u16,
-    pub total_move_number: u16,
-    pub history: History,
-    pub turn: Player,
+    pub board: Board,
     pub analyze_info: Option<AnalyzeInfo>,
+    pub mode: GobanMode,
+    pub dead_stones: HashSet<Point>,
+    pub score: Option<Score>,
+    pub show_tactics: bool,
+    pub show_influence: bool,
+    pub orientation: Orientation,
+    pub move_numbers: MoveNumbers,
+    pub undo: Undo,
+    // read once per position while the overlay is shown, never while drawing
+    tactics: TacticsCache,
 }
 
 impl Default for Goban {
     fn default() -> Self {
+        Goban::new(19)
+    }
+}
+
+impl From<Board> for Goban {
+    fn from(board: Board) -> Self {
         Self {
-            stones: vec![Stone::default(); 19*19 as usize],
-            last_move: None,
-            ko: None,
-            current_move_number: 0,
-            total_move_number: 0,
-            history: History::default(),
-            turn: Player::Black,
+            board,
             analyze_info: None,
+            mode: GobanMode::Play,
+            dead_stones: HashSet::new(),
+            score: None,
+            show_tactics: false,
+            show_influence: false,
+            orientation: Orientation::default(),
+            move_numbers: MoveNumbers::Off,
+            undo: Undo::default(),
+            tactics: None,
         }
     }
 }
 
+impl From<History> for Goban {
+    fn from(history: History) -> Self {
+        Goban::from(Board::from(history))
+    }
+}
+
 impl Goban {
+    pub fn new(board_size: u32) -> Self {
+        Goban::from(Board::new(board_size))
+    }
+
     pub fn view<'a>(&'a self) -> Element<'a, Message> {
         canvas(self)
             .width(iced::Length::Fill)
@@ -256,14 +366,85 @@ impl Goban {
             .into()
     }
 
+    fn screen(&self, p: Point) -> Point {
+        self.orientation.to_screen(p, self.board.board_size)
+    }
+
+    // letters and numbers in the margin, a turned board shows row numbers above and below
+    fn draw_labels(&self, frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32) {
+        let board_size = self.board.board_size;
+        let label = |a: Point, b: Point| {
+            let (a, b) = (self.orientation.to_board(a, board_size), self.orientation.to_board(b, board_size));
+            if a.x == b.x {
+                Point::column_label(a.x).to_string()
+            } else {
+                Point::row_label(a.y, board_size).to_string()
+            }
+        };
+        let side = rect.height;
+        for i in 0..board_size {
+            let along = rect.x + (i + 1) as f32 * spacing;
+            let column = label(Point::new(i, 0), Point::new(i, 1));
+            let row = label(Point::new(0, i), Point::new(1, i));
+            for (content, x, y) in [
+                (&column, along, rect.y + spacing/4.0),
+                (&column, along, rect.y + side - spacing/4.0),
+                (&row, rect.x + spacing/4.0, rect.y + (i + 1) as f32 * spacing),
+                (&row, rect.x + side - spacing/4.0, rect.y + (i + 1) as f32 * spacing),
+            ] {
+                let mut text = canvas::Text::default();
+                text.content = content.clone();
+                text.size = spacing/3.0;
+                text.horizontal_alignment = iced::alignment::Horizontal::Center;
+                text.vertical_alignment = iced::alignment::Vertical::Center;
+                text.position = iced::Point { x, y };
+                frame.fill_text(text);
+            }
+        }
+    }
+
+    // the number shown on each stone still on the board, a point played again shows its last move
+    fn numbered_stones(&self) -> HashMap<usize, u32> {
+        let history = &self.board.history;
+        let moves = history.numbered_moves();
+        let from = match self.move_numbers {
+            MoveNumbers::Off => return HashMap::new(),
+            MoveNumbers::All => 0,
+            MoveNumbers::Last(n) => (moves.len() as u32).saturating_sub(n),
+            MoveNumbers::SinceBranch => history.branch_move_number(),
+        };
+        let mut numbers = HashMap::new();
+        for (number, mov) in moves.into_iter().filter(|(number, _)| *number > from) {
+            if let Some(i) = mov.index {
+                numbers.insert(i, number);
+            }
+        }
+        numbers.retain(|i, _| self.board.stones[*i].visible);
+        numbers
+    }
+
+    fn draw_move_numbers(&self, frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32, numbers: &HashMap<usize, u32>) {
+        for (i, number) in numbers {
+            let p = self.screen(Board::idx_to_coord(*i, self.board.board_size));
+            let mut text = canvas::Text::default();
+            text.content = number.to_string();
+            text.size = if *number >= 100 { spacing/3.0 } else { spacing/2.2 };
+            text.color = player_color(self.board.stones[*i].color.opponent());
+            text.horizontal_alignment = iced::alignment::Horizontal::Center;
+            text.vertical_alignment = iced::alignment::Vertical::Center;
+            text.position = iced::Point { x: rect.x + (p.x+1) as f32 * spacing, y: rect.y + (p.y+1) as f32 * spacing };
+            frame.fill_text(text);
+        }
+    }
+
     fn stones_to_path(&self, rect: &Rectangle) -> Vec<(Path, iced::Color)> {
         let size = rect.height;
-        let size_stone = size/20.0;
-        let radius = size/41.8;
-        self.stones.iter().enumerate().filter(|(_, s)| s.visible).map(|(i, s)| {
-            let coord = Goban::idx_to_coord(i);
+        let size_stone = size/(self.board.board_size + 1) as f32;
+        let radius = size_stone/2.09;
+        self.board.stones.iter().enumerate().filter(|(_, s)| s.visible).map(|(i, s)| {
+            let coord = self.screen(Board::idx_to_coord(i, self.board.board_size));
             (Path::circle(iced::Point {x: rect.x + (coord.x + 1) as f32 * size_stone ,y:  rect.y + (coord.y + 1) as f32 * size_stone}, radius),
-             s.color.into())
+             player_color(s.color))
         }).collect()
     }
 
@@ -275,20 +456,26 @@ impl Goban {
         };
         let point = iced::Point::new((rect.width - size)/2.0, (rect.height - size) / 2.0);
         let square = Rectangle::new(point, iced::Size { width: size, height: size });
-        let pos = Point::new((((position.x - square.x) / (size/20.0)-0.0).round() - 1.0) as u32,
-                             (((position.y - size/40.0 - square.y) / (size/20.0)+0.45).round() - 1.0) as u32);
-
-        if (pos.x < 19 && pos.y < 19) && (position.x >= square.x && position.y >= square.y) {
-            let tmp_point = Goban::coord_to_idx(pos);
-            if !self.stones[tmp_point].visible {
-                let mut stones = self.stones.clone();
-                stones[tmp_point] = Stone::new(self.turn);
-
-                if Goban::is_legal_move(&stones, &self.turn, self.last_move).is_ok() {
-                    state.hover = match self.turn {
-                        Player::Black => Some(pos),
-                        Player::White => Some(pos),
-                    };
+        let spacing = size/(self.board.board_size + 1) as f32;
+        let pos = Point::new((((position.x - square.x) / spacing-0.0).round() - 1.0) as u32,
+                             (((position.y - spacing/2.0 - square.y) / spacing+0.45).round() - 1.0) as u32);
+
+        if (pos.x < self.board.board_size && pos.y < self.board.board_size) && (position.x >= square.x && position.y >= square.y) {
+            let pos = self.orientation.to_board(pos, self.board.board_size);
+            let tmp_point = Board::coord_to_idx(pos, self.board.board_size);
+            if matches!(self.mode, GobanMode::Edit | GobanMode::Markup(_)) {
+                state.hover = Some(pos);
+            } else if self.mode == GobanMode::MarkDead {
+                state.hover = if self.board.stones[tmp_point].visible {
+                    Some(pos)
+                } else {
+                    None
+                };
+            } else if !self.board.stones[tmp_point].visible {
+                if self.board.is_legal(pos) {
+                    state.hover = Some(pos);
+                } else {
+                    state.hover = None;
                 }
             } else {
                 state.hover = None;
@@ -296,6 +483,10 @@ impl Goban {
         } else {
             state.hover = None;
         }
+        state.self_atari = match state.hover {
+            Some(p) if self.mode == GobanMode::Play => tactics::is_self_atari(&self.board, p),
+            _ => false,
+        };
     }
 }
 
@@ -305,17 +496,149 @@ impl Goban {
         self.analyze_info = None;
         match message {
             Message::Goban(event) => match event {
+                GobanEvent::ToggleMarkDead => {
+                    match self.mode {
+                        GobanMode::MarkDead => self.leave_mark_dead(),
+                        _ => {
+                            self.mode = GobanMode::MarkDead;
+                            self.dead_stones = self.marked_dead_stones();
+                            return Some(Message::Score)
+                        },
+                    }
+                },
+                GobanEvent::Rotate => {
+                    self.orientation.rotate();
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::Flip => {
+                    self.orientation.flip();
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::ToggleWhiteSide => {
+                    self.orientation.toggle_white_side();
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::SetMoveNumbers(move_numbers) => {
+                    self.move_numbers = move_numbers;
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::ToggleInfluence => {
+                    self.show_influence = !self.show_influence;
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::ToggleTactics => {
+                    self.show_tactics = !self.show_tactics;
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::ToggleEdit => {
+                    self.leave_mark_dead();
+                    self.mode = match self.mode {
+                        GobanMode::Edit => GobanMode::Play,
+                        _ => GobanMode::Edit,
+                    };
+                },
+                GobanEvent::SetMarkupTool(tool) => {
+                    self.leave_mark_dead();
+                    self.mode = if self.mode == GobanMode::Markup(tool) {
+                        GobanMode::Play
+                    } else {
+                        GobanMode::Markup(tool)
+                    };
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::Undo | GobanEvent::Redo => {
+                    self.leave_mark_dead();
+                    let from = self.engine_trail();
+                    let edit = if let GobanEvent::Undo = event {
+                        self.undo.undo(&mut self.board)
+                    } else {
+                        self.undo.redo(&mut self.board)
+                    };
+                    // the edit is replayed in place, the engine follows the board along the tree
+                    match edit.map(Edit::is_setup) {
+                        Some(true) => return Some(Message::EngineCommand(crate::EngineCommand::EngineSetPosition(self.engine_position()))),
+                        Some(false) => return Some(self.engine_path(&from)),
+                        None => (),
+                    }
+                },
+                GobanEvent::Mark(tool, from, to) => {
+                    let node = self.board.history.current_index;
+                    let before = self.board.history.moves[node].markup.clone();
+                    let (from, to) = (Board::coord_to_idx(from, self.board.board_size), Board::coord_to_idx(to, self.board.board_size));
+                    let markup = match tool {
+                        MarkupTool::Shape(shape) => Markup::Shape(shape, to),
+                        MarkupTool::Label => Markup::Label(to, self.board.history.next_label()),
+                        MarkupTool::Arrow => Markup::Arrow(from, to),
+                        MarkupTool::Line => Markup::Line(from, to),
+                    };
+                    self.board.history.toggle_markup(markup);
+                    self.undo.record(Edit::Markup { node, before, after: self.board.history.moves[node].markup.clone() });
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::Edit(p, color) => {
+                    let node = self.board.history.current_index;
+                    let before = Edit::setup_state(&self.board.history, node);
+                    self.board.edit(Board::coord_to_idx(p, self.board.board_size), color);
+                    self.undo.record(Edit::setup(&self.board.history, node, before));
+                    return Some(Message::EngineCommand(crate::EngineCommand::EngineSetPosition(self.engine_position())))
+                },
+                GobanEvent::SetTurn(player) => {
+                    let node = self.board.history.current_index;
+                    let before = Edit::setup_state(&self.board.history, node);
+                    if self.board.set_turn(player) {
+                        self.undo.record(Edit::setup(&self.board.history, node, before));
+                        // the running analysis starts again for the player now to move
+                        return Some(Message::RefreshAnalyze)
+                    }
+                },
+                GobanEvent::ToggleDead(p) => {
+                    // unconditionally alive groups can't be dead whatever was agreed
+                    if life::alive_stones(&self.board).contains(&p) {
+                        return None
+                    }
+                    if let Some(group) = self.board.find_groups().into_iter().find(|g| g.stones.contains(&p)) {
+                        if self.dead_stones.contains(&p) {
+                            group.stones.iter().for_each(|s| { self.dead_stones.remove(s); });
+                        } else {
+                            self.dead_stones.extend(group.stones);
+                        }
+                    }
+                    return Some(Message::Score)
+                },
+                GobanEvent::MarkDeadFromOwnership => {
+                    if let Some(info) = &analyze_info {
+                        self.mode = GobanMode::MarkDead;
+                        self.dead_stones.clear();
+                        let alive = life::alive_stones(&self.board);
+                        for group in self.board.find_groups().into_iter().filter(|g| !alive.contains(&g.stones[0])) {
+                            // a group is dead when the engine gives its points to the opponent
+                            let ownership = group.stones.iter()
+                                .filter_map(|p| info.black_ownership_at(self.board.turn, *p, self.board.board_size))
+                                .sum::<f32>();
+                            let ownership = if group.team == Player::Black { ownership } else { -ownership };
+                            if ownership < 0.0 {
+                                self.dead_stones.extend(group.stones);
+                            }
+                        }
+                    }
+                    self.analyze_info = analyze_info;
+                    return Some(Message::Score)
+                },
                 GobanEvent::Play(p, s) => {
-                    if self.history.set_variation_to_move(Goban::coord_to_idx(p)) {
-                        self.next_state();
+                    self.leave_mark_dead();
+                    if self.board.history.set_variation_to_move(Some(Board::coord_to_idx(p, self.board.board_size))) {
+                        self.board.next_state();
+                    } else if self.board.is_legal(p) {
+                        self.board.play(p, s);
+                        self.undo.record(Edit::added(&self.board.history));
                     } else {
-                        self.play(p, s);
+                        return None
                     }
                     let winrate = if analyze_info.is_some() {
-                        let mut pred_turn = self.turn;
+                        let mut pred_turn = self.board.turn;
                         pred_turn.next();
                         if let Some(w) = analyze_info.unwrap().winrate_of(pred_turn, p) {
-                            Some((self.current_move_number as u64, w))
+                            Some((self.board.current_move_number as u64, w))
                         } else {
                             None
                         }
@@ -323,15 +646,71 @@ impl Goban {
                     else {
                         None
                     };
-                    return Some(Message::EngineCommand(crate::EngineCommand::EnginePlay(match self.turn { Player::Black => Player::White, Player::White => Player::Black}, p, winrate)))
+                    return Some(Message::EngineCommand(crate::EngineCommand::EnginePlay(match self.board.turn { Player::Black => Player::White, Player::White => Player::Black}, Some(p), winrate)))
+                },
+                GobanEvent::Pass => {
+                    self.leave_mark_dead();
+                    if self.board.history.set_variation_to_move(None) {
+                        self.board.next_state();
+                    } else {
+                        self.board.pass();
+                        self.undo.record(Edit::added(&self.board.history));
+                    }
+                    return Some(Message::EngineCommand(crate::EngineCommand::EnginePlay(match self.board.turn { Player::Black => Player::White, Player::White => Player::Black}, None, None)))
                 },
                 GobanEvent::NextState => {
-                    if self.next_state() {
-                        return Some(Message::EngineCommand(crate::EngineCommand::EnginePlay(match self.turn { Player::Black => Player::White, Player::White => Player::Black}, self.last_move.unwrap(), None)))
+                    self.leave_mark_dead();
+                    if self.board.next_state() {
+                        if self.board.history.moves[self.board.history.current_index].is_setup() {
+                            return Some(Message::EngineCommand(crate::EngineCommand::EngineSetPosition(self.engine_position())))
+                        }
+                        // last_move is None when the move we just replayed is a pass
+                        return Some(Message::EngineCommand(crate::EngineCommand::EnginePlay(match self.board.turn { Player::Black => Player::White, Player::White => Player::Black}, self.board.last_move, None)))
+                    }
+                },
+                GobanEvent::GoTo(index) => return self.jump(index),
+                GobanEvent::GoToStart => return self.jump(0.into()),
+                GobanEvent::GoToEnd => return self.jump(self.board.history.end_of_line()),
+                GobanEvent::GoToMove(n) => return self.jump(self.board.history.move_index(n)),
+                GobanEvent::NextFork => if let Some(index) = self.board.history.next_fork() {
+                    return self.jump(index)
+                },
+                GobanEvent::PreviousFork => if let Some(index) = self.board.history.previous_fork() {
+                    return self.jump(index)
+                },
+                GobanEvent::DeleteVariation => {
+                    self.leave_mark_dead();
+                    let was_setup = self.board.history.moves[self.board.history.current_index].is_setup();
+                    if let Some(subtree) = self.board.delete_variation() {
+                        self.undo.record(Edit::RemovedSubtree(subtree));
+                        if was_setup {
+                            return Some(self.engine_replay())
+                        }
+                        return Some(Message::EngineCommand(crate::EngineCommand::EngineUndo))
+                    }
+                },
+                GobanEvent::PromoteVariation => {
+                    let before = Edit::line_order(&self.board.history);
+                    if self.board.history.promote_to_main_line() {
+                        self.undo.record(Edit::order(before, &self.board.history));
+                    }
+                    self.analyze_info = analyze_info;
+                },
+                GobanEvent::ShiftVariation(earlier) => {
+                    let before = Edit::line_order(&self.board.history);
+                    if self.board.history.shift_variation(earlier) {
+                        self.undo.record(Edit::order(before, &self.board.history));
                     }
+                    self.analyze_info = analyze_info;
                 },
                 GobanEvent::PreviousState => {
-                    if self.previous_state() {
+                    self.leave_mark_dead();
+                    // the engine forgets its history when given a position, it has to replay the game
+                    let was_setup = self.board.history.moves[self.board.history.current_index].is_setup();
+                    if self.board.previous_state() {
+                        if was_setup {
+                            return Some(self.engine_replay())
+                        }
                         return Some(Message::EngineCommand(crate::EngineCommand::EngineUndo))
                     }
                 },
@@ -342,6 +721,249 @@ impl Goban {
     }
 }
 
+impl Goban {
+    pub fn engine_position(&self) -> Vec<(Player, Point)> {
+        self.board.stones.iter().enumerate()
+            .filter(|(_, s)| s.visible)
+            .map(|(i, s)| (s.color, Board::idx_to_coord(i, self.board.board_size)))
+            .collect()
+    }
+
+    // commands bringing a cleared engine to the current node
+    pub fn engine_replay(&self) -> Message {
+        let mut position: Vec<Option<Player>> = vec![None; self.board.stones.len()];
+        let mut commands = Vec::new();
+        for index in self.board.history.path_to_current() {
+            let mov = &self.board.history.moves[index];
+            if mov.is_setup() {
+                for s in &mov.setup {
+                    position[s.index] = s.after;
+                }
+                // gtp wants two handicap stones at least, a lone one is set like any other stones
+                if index == 0.into() && mov.is_handicap() && mov.setup.len() > 1 && mov.player == Player::Black {
+                    commands.push(crate::EngineCommand::EngineHandicap(mov.setup.iter()
+                        .map(|s| Board::idx_to_coord(s.index, self.board.board_size))
+                        .collect()));
+                    continue
+                }
+                commands.push(crate::EngineCommand::EngineSetPosition(position.iter().enumerate()
+                    .filter_map(|(i, c)| c.map(|c| (c, Board::idx_to_coord(i, self.board.board_size))))
+                    .collect()));
+            } else if index != 0.into() {
+                if let Some(i) = mov.index {
+                    position[i] = Some(mov.player);
+                }
+                for g in &mov.groups {
+                    for p in &g.stones {
+                        position[Board::coord_to_idx(*p, self.board.board_size)] = None;
+                    }
+                }
+                commands.push(crate::EngineCommand::EnginePlay(mov.player, mov.index.map(|i| Board::idx_to_coord(i, self.board.board_size)), None));
+            }
+        }
+        Message::EngineCommand(crate::EngineCommand::EngineReplay(commands))
+    }
+
+    // the line the engine stands on, root first, and which of its nodes are setups
+    // taken before moving, the nodes may be gone by the time the engine follows
+    pub fn engine_trail(&self) -> Vec<(NodeIndex<u32>, bool)> {
+        self.board.history.path_to_current().into_iter()
+            .map(|i| (i, self.board.history.moves[i].is_setup()))
+            .collect()
+    }
+
+    // commands taking the engine from its trail to the current node, back to their common ancestor then forward
+    // the engine forgets its history at a setup, going through one means replaying the game
+    pub fn engine_path(&self, from: &[(NodeIndex<u32>, bool)]) -> Message {
+        let history = &self.board.history;
+        let path = history.path_to_current();
+        let shared = from.iter().zip(&path).take_while(|((a, _), b)| a == *b).count();
+        if from[shared..].iter().any(|(_, setup)| *setup) || path[shared..].iter().any(|i| history.moves[*i].is_setup()) {
+            return self.engine_replay()
+        }
+        let mut commands = vec![crate::EngineCommand::EngineUndo; from.len() - shared];
+        for i in &path[shared..] {
+            let mov = &history.moves[*i];
+            commands.push(crate::EngineCommand::EnginePlay(mov.player, mov.index.map(|i| Board::idx_to_coord(i, self.board.board_size)), None));
+        }
+        Message::EngineCommand(crate::EngineCommand::EngineSync(commands))
+    }
+
+    // the board walks there at once and the engine follows in a single batch of commands
+    fn jump(&mut self, index: NodeIndex<u32>) -> Option<Message> {
+        self.leave_mark_dead();
+        let from = self.engine_trail();
+        if index != self.board.history.current_index && self.board.go_to(index) {
+            return Some(self.engine_path(&from))
+        }
+        None
+    }
+
+    fn edit_message(&self, p: Point, color: Player) -> Message {
+        let color = if self.board.stones[Board::coord_to_idx(p, self.board.board_size)].visible {
+            None
+        } else {
+            Some(color)
+        };
+        Message::Goban(crate::GobanEvent::Edit(p, color))
+    }
+
+    fn leave_mark_dead(&mut self) {
+        if self.mode == GobanMode::MarkDead {
+            self.mode = GobanMode::Play;
+        }
+        self.dead_stones.clear();
+        self.score = None;
+    }
+
+    // stones standing in the opponent's territory saved on the current node
+    fn marked_dead_stones(&self) -> HashSet<Point> {
+        let alive = life::alive_stones(&self.board);
+        self.board.history.moves[self.board.history.current_index].territory.iter()
+            .filter(|(owner, i)| self.board.stones[*i].visible && self.board.stones[*i].color != *owner)
+            .map(|(_, i)| Board::idx_to_coord(*i, self.board.board_size))
+            .filter(|p| !alive.contains(p))
+            .collect()
+    }
+
+    // marks of the current node, drawn in a color standing out from the stone below
+    fn draw_markup(&self, frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32, markup_start: Option<Point>) {
+        let center = |i: usize| {
+            let p = self.screen(Board::idx_to_coord(i, self.board.board_size));
+            iced::Point { x: rect.x + (p.x+1) as f32 * spacing, y: rect.y + (p.y+1) as f32 * spacing }
+        };
+        let color = |i: usize| match self.board.stones[i] {
+            Stone { visible: true, color: Player::Black } => iced::Color::WHITE,
+            _ => iced::Color::BLACK,
+        };
+        let stroke = |color: iced::Color| canvas::Stroke::default().with_color(color).with_width(2.0);
+        // arrows and lines cross several points, they get a color of their own
+        let line_color = iced::Color::from_rgb(0.8, 0.0, 0.0);
+        let r = spacing/4.0;
+        for mark in &self.board.history.moves[self.board.history.current_index].markup {
+            match mark {
+                Markup::Shape(shape, i) => {
+                    let c = center(*i);
+                    let path = match shape {
+                        Shape::Triangle => Path::new(|b| {
+                            b.move_to(iced::Point { x: c.x, y: c.y - r });
+                            b.line_to(iced::Point { x: c.x + r*0.87, y: c.y + r/2.0 });
+                            b.line_to(iced::Point { x: c.x - r*0.87, y: c.y + r/2.0 });
+                            b.close();
+                        }),
+                        Shape::Square => Path::rectangle(iced::Point { x: c.x - r*0.8, y: c.y - r*0.8 }, iced::Size { width: r*1.6, height: r*1.6 }),
+                        Shape::Circle => Path::circle(c, r),
+                        Shape::Cross => Path::new(|b| {
+                            b.move_to(iced::Point { x: c.x - r*0.8, y: c.y - r*0.8 });
+                            b.line_to(iced::Point { x: c.x + r*0.8, y: c.y + r*0.8 });
+                            b.move_to(iced::Point { x: c.x + r*0.8, y: c.y - r*0.8 });
+                            b.line_to(iced::Point { x: c.x - r*0.8, y: c.y + r*0.8 });
+                        }),
+                    };
+                    frame.stroke(&path, stroke(color(*i)));
+                },
+                Markup::Label(i, label) => {
+                    let c = center(*i);
+                    // hide the lines so the label can be read on an empty point
+                    if !self.board.stones[*i].visible {
+                        frame.fill(&Path::circle(c, spacing/2.5), BOARD_COLOR);
+                    }
+                    let mut text = canvas::Text::default();
+                    text.content = label.clone();
+                    text.size = if label.chars().count() > 2 { spacing/3.0 } else { spacing/2.2 };
+                    text.color = color(*i);
+                    text.horizontal_alignment = iced::alignment::Horizontal::Center;
+                    text.vertical_alignment = iced::alignment::Vertical::Center;
+                    text.position = c;
+                    frame.fill_text(text);
+                },
+                Markup::Arrow(from, to) => {
+                    let (a, b) = (center(*from), center(*to));
+                    let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt().max(1.0);
+                    let (dx, dy) = ((b.x - a.x) / length * r, (b.y - a.y) / length * r);
+                    frame.stroke(&Path::line(a, b), stroke(line_color));
+                    frame.stroke(&Path::line(b, iced::Point { x: b.x - dx - dy/2.0, y: b.y - dy + dx/2.0 }), stroke(line_color));
+                    frame.stroke(&Path::line(b, iced::Point { x: b.x - dx + dy/2.0, y: b.y - dy - dx/2.0 }), stroke(line_color));
+                },
+                Markup::Line(from, to) => frame.stroke(&Path::line(center(*from), center(*to)), stroke(line_color)),
+            }
+        }
+        if let Some(p) = markup_start {
+            frame.fill(&Path::circle(center(Board::coord_to_idx(p, self.board.board_size)), spacing/8.0), line_color);
+        }
+    }
+
+    fn tactics_key(&self) -> (u64, NodeIndex<u32>, Player) {
+        (self.board.hash, self.board.history.current_index, self.board.turn)
+    }
+
+    // reads the position once the overlay is on and the board moved, the result waits for the next redraws
+    pub fn read_tactics(&mut self) {
+        let key = self.tactics_key();
+        if self.show_tactics && self.tactics.as_ref().map(|(k, _)| *k) != Some(key) {
+            self.tactics = Some((key, Tactics::read(&self.board)));
+        }
+    }
+
+    // rings around stones in atari, an L where a ladder starts: red when it works, green when it is broken, grey when the reading gave up
+    fn draw_tactics(&self, frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32) {
+        let tactics = match &self.tactics {
+            Some((key, tactics)) if *key == self.tactics_key() => tactics,
+            _ => return,
+        };
+        let stroke = canvas::Stroke::default()
+            .with_color(iced::Color::from_rgb(1.0, 0.0, 0.0))
+            .with_width(2.0);
+        for group in &tactics.atari {
+            for p in &group.stones {
+                let p = self.screen(*p);
+                let center = iced::Point { x: rect.x + (p.x+1) as f32 * spacing, y: rect.y + (p.y+1) as f32 * spacing };
+                frame.stroke(&Path::circle(center, spacing/2.5), stroke.clone());
+            }
+        }
+        for ladder in &tactics.ladders {
+            let mut text = canvas::Text::default();
+            text.content = String::from("L");
+            text.size = spacing/2.0;
+            text.color = match ladder.works {
+                Some(true) => iced::Color::from_rgb(0.8, 0.0, 0.0),
+                Some(false) => iced::Color::from_rgb(0.0, 0.6, 0.0),
+                None => iced::Color::from_rgb(0.5, 0.5, 0.5),
+            };
+            text.horizontal_alignment = iced::alignment::Horizontal::Center;
+            text.vertical_alignment = iced::alignment::Vertical::Center;
+            let start = self.screen(ladder.start);
+            text.position = iced::Point { x: rect.x + (start.x+1) as f32 * spacing, y: rect.y + (start.y+1) as f32 * spacing };
+            frame.fill_text(text);
+        }
+    }
+
+    fn draw_mark_dead(&self, frame: &mut canvas::Frame, rect: &Rectangle, spacing: f32) {
+        if let Some(score) = &self.score {
+            for (owner, i) in &score.territory {
+                let p = self.screen(Board::idx_to_coord(*i, self.board.board_size));
+                frame.fill(&Path::rectangle(iced::Point { x: rect.x + (p.x+1) as f32 * spacing - spacing/6.0, y: rect.y + (p.y+1) as f32 * spacing - spacing/6.0}, iced::Size { width: spacing/3.0, height: spacing/3.0 }), player_color(*owner));
+            }
+        }
+        let stroke = canvas::Stroke::default()
+            .with_color(iced::Color::from_rgb(1.0, 0.0, 0.0))
+            .with_width(2.0);
+        for p in &self.dead_stones {
+            let p = self.screen(*p);
+            let center = iced::Point { x: rect.x + (p.x+1) as f32 * spacing, y: rect.y + (p.y+1) as f32 * spacing };
+            let d = spacing/4.0;
+            frame.stroke(&Path::line(iced::Point { x: center.x - d, y: center.y - d }, iced::Point { x: center.x + d, y: center.y + d }), stroke.clone());
+            frame.stroke(&Path::line(iced::Point { x: center.x + d, y: center.y - d }, iced::Point { x: center.x - d, y: center.y + d }), stroke.clone());
+        }
+        // a green dot on groups that can't be marked dead
+        for p in life::alive_stones(&self.board) {
+            let p = self.screen(p);
+            let center = iced::Point { x: rect.x + (p.x+1) as f32 * spacing, y: rect.y + (p.y+1) as f32 * spacing };
+            frame.fill(&Path::circle(center, spacing/8.0), iced::Color::from_rgb(0.0, 0.8, 0.0));
+        }
+    }
+}
+
 impl<'a> canvas::Program<Message> for Goban {
     type State = GobanState;
 
@@ -366,17 +988,17 @@ impl<'a> canvas::Program<Message> for Goban {
         let square = Path::rectangle(point, iced::Size { width: square_size, height: square_size });
 
         // fill Goban background
-        frame.fill(&square, iced::Color::from_rgb8(219, 185, 52));
+        frame.fill(&square, BOARD_COLOR);
 
         // add the lines
-        let spacing = square_size / 20.0;
+        let spacing = square_size / (self.board.board_size + 1) as f32;
         let x_right = point.x + square_size - spacing;
         let y_down = point.y + square_size - spacing;
 
-        let mut lines: Vec<Path> = (1..20).map(|x|
+        let mut lines: Vec<Path> = (1..=self.board.board_size).map(|x|
             Path::line(iced::Point { x: point.x + spacing, y: point.y + (x as f32 * spacing)}, iced::Point {x: x_right, y: point.y + (x as f32 * spacing)})).collect();
         
-        let mut vertical_lines: Vec<Path> = (1..20).map(|x|
+        let mut vertical_lines: Vec<Path> = (1..=self.board.board_size).map(|x|
             Path::line(iced::Point { x: point.x + (x as f32 * spacing), y: point.y + spacing}, iced::Point {x: point.x + (x as f32 * spacing), y: y_down})).collect();
         
         lines.append(&mut vertical_lines);
@@ -389,39 +1011,41 @@ impl<'a> canvas::Program<Message> for Goban {
             frame.stroke(&line, stroke.clone());
         }
 
-        let small_radius = square_size/250.0;
-        frame.fill(&Path::circle(iced::Point::new(point.x + 4.0 * spacing, point.y + 4.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(x_right - 3.0 * spacing, point.y + 4.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(point.x + 4.0 * spacing, y_down - 3.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(x_right - 3.0 * spacing, y_down - 3.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(point.x + 10.0 * spacing, point.y + 4.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(point.x + 4.0 * spacing, point.y + 10.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(x_right - 3.0 * spacing, point.y + 10.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(point.x + 10.0 * spacing, y_down - 3.0 * spacing), small_radius), iced::Color::BLACK);
-        frame.fill(&Path::circle(iced::Point::new(point.x + 10.0 * spacing, point.y + 10.0 * spacing), small_radius), iced::Color::BLACK);
+        let small_radius = spacing/12.5;
+        for hoshi in Board::star_points(self.board.board_size) {
+            let hoshi = self.screen(hoshi);
+            frame.fill(&Path::circle(iced::Point::new(point.x + (hoshi.x + 1) as f32 * spacing, point.y + (hoshi.y + 1) as f32 * spacing), small_radius), iced::Color::BLACK);
+        }
 
         let rectangle = Rectangle::new(point, iced::Size::new(square_size, square_size));
+        self.draw_labels(&mut frame, &rectangle, spacing);
 
-        let variations = self.history.get_possible_moves();
+        let variations = self.board.history.get_possible_moves();
         if variations.len() > 1 {
-            let stone = match self.turn {
+            let stone = match self.board.turn {
                 Player::Black => Stone::black(),
                 Player::White => Stone::white(),
             };
-            for v in variations {
-                let p = Goban::idx_to_coord(v);
-                let (path, color) = stone.possibilty(&rectangle, p);
+            for v in variations.into_iter().flatten() {
+                let p = self.screen(Board::idx_to_coord(v, self.board.board_size));
+                let (path, color) = stone.possibilty(&rectangle, p, self.board.board_size);
                 frame.fill(&path, color);
             }
         }
 
-        if state.hover.is_some() {
-            let p = state.hover.as_ref().unwrap();
-            let (path, color) = Stone::new(self.turn).hover(&rectangle, p);
+        if state.hover.is_some() && self.mode == GobanMode::Play {
+            let p = self.screen(state.hover.unwrap());
+            let (path, color) = Stone::new(self.board.turn).hover(&rectangle, &p, self.board.board_size);
             frame.fill(&path, color);
-            let border = canvas::Stroke::default()
-                .with_color(iced::Color::BLACK)
-                .with_width(1.0);
+            let border = if state.self_atari {
+                canvas::Stroke::default()
+                    .with_color(iced::Color::from_rgb(1.0, 0.0, 0.0))
+                    .with_width(3.0)
+            } else {
+                canvas::Stroke::default()
+                    .with_color(iced::Color::BLACK)
+                    .with_width(1.0)
+            };
             frame.stroke(&path, border);
         }
 
@@ -434,25 +1058,44 @@ impl<'a> canvas::Program<Message> for Goban {
             frame.stroke(path, border);
         }
 
+        let numbers = self.numbered_stones();
+        self.draw_move_numbers(&mut frame, &rectangle, spacing, &numbers);
+        self.draw_markup(&mut frame, &rectangle, spacing, state.markup_start);
+
+        if self.mode == GobanMode::MarkDead {
+            self.draw_mark_dead(&mut frame, &rectangle, spacing);
+        }
+
+        if self.show_tactics {
+            self.draw_tactics(&mut frame, &rectangle, spacing);
+        }
+
+        // the engine knows better, the estimate only shows without its analysis
+        if self.show_influence && self.analyze_info.is_none() {
+            let ownership = Influence::new(&self.board).ownership();
+            draw_ownership(&mut frame, &rectangle, spacing, ownership.into_iter().enumerate()
+                .map(|(i, o)| (self.screen(Board::idx_to_coord(i, self.board.board_size)), o)));
+        }
+
         if self.analyze_info.is_some() {
-            self.analyze_info.as_ref().unwrap().draw(&mut frame, &rectangle, rectangle.height, self.turn);
+            self.analyze_info.as_ref().unwrap().draw(&mut frame, &rectangle, rectangle.height, self.board.turn, self.board.board_size, self.orientation);
         }
 
-        if self.ko.is_some() {
-            let ko = self.ko.unwrap();
-            let size = rectangle.height;
+        if self.board.ko.is_some() {
+            let ko = self.screen(self.board.ko.unwrap());
             let border = canvas::Stroke::default()
                 .with_color(iced::Color::BLACK)
                 .with_width(3.0);
-            frame.stroke(&Path::rectangle(iced::Point { x: rectangle.x + (ko.x+1) as f32 * size/20.0 - size/84.0, y: rectangle.y + (ko.y+1) as f32 * size/20.0 - size/84.0}, iced::Size { width: size/42.0, height: size/42.0 }), border);
+            frame.stroke(&Path::rectangle(iced::Point { x: rectangle.x + (ko.x+1) as f32 * spacing - spacing/4.2, y: rectangle.y + (ko.y+1) as f32 * spacing - spacing/4.2}, iced::Size { width: spacing/2.1, height: spacing/2.1 }), border);
         }
 
-        if self.last_move.is_some() {
-            let coord = self.last_move.unwrap();
-            let size = rectangle.height;
-            let size_stone = size/20.0;
-            let radius = size/60.0;
-            let color = match self.turn {
+        // a numbered stone is already easy to find
+        let last_numbered = self.board.last_move.is_some_and(|p| numbers.contains_key(&Board::coord_to_idx(p, self.board.board_size)));
+        if self.board.last_move.is_some() && !last_numbered {
+            let coord = self.screen(self.board.last_move.unwrap());
+            let size_stone = spacing;
+            let radius = spacing/3.0;
+            let color = match self.board.turn {
                 Player::Black => iced::Color::new(0.0, 0.0, 0.0, 1.0),
                 Player::White => iced::Color::new(1.0, 1.0, 1.0, 1.0),
             }; 
@@ -480,12 +1123,35 @@ impl<'a> canvas::Program<Message> for Goban {
                         iced::mouse::Button::Left => {
                             if state.hover.is_some() {
                                 let p = state.hover.as_ref().unwrap().clone();
-                                let s = Stone::new(self.turn);
-                                state.hover = None;
-                                return (canvas::event::Status::Captured, Some(Message::Goban(crate::GobanEvent::Play(p, s))))
+                                match self.mode {
+                                    GobanMode::MarkDead => return (canvas::event::Status::Captured, Some(Message::Goban(crate::GobanEvent::ToggleDead(p)))),
+                                    GobanMode::Edit => return (canvas::event::Status::Captured, Some(self.edit_message(p, Player::Black))),
+                                    GobanMode::Markup(tool) if tool.two_points() => match state.markup_start.take() {
+                                        Some(start) if start != p => return (canvas::event::Status::Captured, Some(Message::Goban(crate::GobanEvent::Mark(tool, start, p)))),
+                                        Some(_) => return (canvas::event::Status::Captured, None),
+                                        None => {
+                                            state.markup_start = Some(p);
+                                            return (canvas::event::Status::Captured, None)
+                                        },
+                                    },
+                                    GobanMode::Markup(tool) => return (canvas::event::Status::Captured, Some(Message::Goban(crate::GobanEvent::Mark(tool, p, p)))),
+                                    GobanMode::Play => {
+                                        let s = Stone::new(self.board.turn);
+                                        state.hover = None;
+                                        return (canvas::event::Status::Captured, Some(Message::Goban(crate::GobanEvent::Play(p, s))))
+                                    },
+                                }
+                            }
+                        },
+                        iced::mouse::Button::Right => {
+                            if let (Some(p), GobanMode::Edit) = (state.hover, self.mode) {
+                                return (canvas::event::Status::Captured, Some(self.edit_message(p, Player::White)))
+                            }
+                            // drops a half drawn arrow or line
+                            if state.markup_start.take().is_some() {
+                                return (canvas::event::Status::Captured, None)
                             }
                         },
-                        iced::mouse::Button::Right => (),
                         _ => (),
                     },
                     iced::mouse::Event::WheelScrolled { delta } => {
@@ -528,244 +1194,3 @@ impl<'a> canvas::Program<Message> for Goban {
         (canvas::event::Status::Ignored, None)
     }
 }
-
-impl Goban {
-    pub fn idx_to_coord(i: usize) -> Point {
-        Point::new(i as u32 / 19, i as u32 % 19)
-    }
-
-    pub fn coord_to_idx(p: Point) -> usize {
-        p.x as usize * 19 + p.y as usize
-    }
-
-    pub fn play(&mut self, p: Point, s: Stone) {
-        // check if move is in history, if it is, use next_state
-        let point = Goban::coord_to_idx(p);
-        self.stones[point] = s;
-        let (dead_groups, _, _) = Goban::find_dead_groups(&self.stones, &self.turn);
-        self.ko = None;
-        if dead_groups.len() == 2 {
-            let mut we_died = false;
-            dead_groups.iter().for_each(|g| {
-                if g.team == self.turn {
-                    we_died = true;
-                } else {
-                    if g.stones.len() == 1 { // we check for a ko
-                        let is_ko = Goban::is_legal_move(&self.stones, match self.turn {
-                            Player::Black => &Player::White,
-                            Player::White => &Player::Black,
-                        }, Some(p)).is_err();
-                        if is_ko {
-                            self.ko = Some(g.stones[0]);
-                        }
-                    }
-                }
-            });
-            if !we_died {
-                self.ko = None;
-            }
-        }
-        let mut enemy_groups = Vec::new();
-        dead_groups.iter().for_each(|g| {
-            if g.team != self.turn {
-                enemy_groups.push(g.clone());
-                for p in &g.stones {
-                    let i = Goban::coord_to_idx(*p);
-                    self.stones[i] = Stone::default();
-                    //add to captures
-                }
-            }
-        });
-        match self.history.push((self.turn.clone(), point, enemy_groups)) {
-            Ok(_) => (),
-            Err(_) => panic!(), // show message something went wrong
-        }
-        self.last_move = Some(p);
-        self.turn.next();
-        self.current_move_number += 1;
-    }
-
-
-    fn find_groups(stones_vec: &Vec<Stone>) -> Vec<Group> {
-        let mut stones = stones_vec.iter().enumerate().filter_map(|(i,s)| {
-            if s.visible {
-                Some((i, s))
-            } else {
-                None
-            }
-        }).collect::<Vec<_>>();
-
-        let mut liberties = Vec::new();
-        let mut stack = Vec::new();
-        let mut seen = HashSet::new();
-        let mut groups = Vec::new();
-        let mut group_stones = Vec::new();
-
-        while let Some((i, s)) = stones.pop() { // take one stone from the board
-            let color = s.color.clone();
-            stack.push(i); // add the stone to the stack of stone to explore
-            seen.insert(Goban::idx_to_coord(i));
-            while let Some(i) = stack.pop() { // while there is stones to explore
-                group_stones.push(Goban::idx_to_coord(i)); // add the stone to the group
-                for p in Goban::surrounding_points(Goban::idx_to_coord(i)) { // for each point around us
-                    if !seen.insert(p) {
-                        continue;
-                    }
-                let s = stones_vec[Goban::coord_to_idx(p)].clone(); // retrieve stone for color
-                    if !s.visible { // if intersection is empty and we have not yet counted this liberty
-                        liberties.push(p); // add to liberties
-                    } else if s.color == color { // if intersection is filled, and it's the same color as us
-                        stack.push(Goban::coord_to_idx(p));
-                        stones.retain(|(x, _s)| {
-                            *x != Goban::coord_to_idx(p)});
-                    }
-                }
-            }
-            let group = Group {
-                stones: group_stones.clone(),
-                liberties: liberties.len() as u64,
-                team: color,
-            };
-            groups.push(group);
-
-            liberties.clear();
-            seen.clear();
-            group_stones.clear();
-        }
-
-        groups
-    }
-
-    fn surrounding_points(p: Point) -> impl Iterator<Item = Point> {
-        let x = p.x as i64;
-        let y = p.y as i64;
-        [(-1, 0), (1, 0), (0, -1), (0, 1)]
-            .iter()
-            .filter_map(move |&(dx, dy)| {
-                if (x + dx) >= 0 && x + dx < 19 as i64 && (y + dy) >= 0 && y + dy < 19 as i64 {
-                    Some(Point::new((x + dx) as u32, (y + dy) as u32))
-                } else {
-                    None
-                }
-            })
-    }
-
-    fn find_dead_groups(stones: &Vec<Stone>, current_turn: &Player) -> (Vec<Group>, bool, bool) { //bool is true if an opponent's group died, second one if we died
-        let mut opponent_died = false;
-        let mut we_died = false;
-        let groups = Goban::find_groups(stones);
-        let dead_groups = groups.into_iter().filter(|g| {
-            if g.liberties == 0 {
-                if g.team != *current_turn {
-                    opponent_died = true;
-                } else if g.team == *current_turn {
-                    we_died = true;
-                }
-                true
-            } else {
-                false
-            }
-        }).collect::<Vec<_>>();
-
-        (dead_groups, opponent_died, we_died)
-    }
-
-    pub fn is_legal_move(stones: &Vec<Stone>, current_turn: &Player, last_move: Option<Point>) -> Result<Vec<Group>, ()> {
-        let (dead_groups, opponent_died, we_died) = Goban::find_dead_groups(stones, current_turn);
-        let mut illegal = false;
-        let dead_groups = dead_groups.into_iter().filter(|group| {
-            if !opponent_died && group.team == *current_turn {
-                illegal = true;
-                false
-            } else if we_died && opponent_died && group.team != *current_turn {
-                if group.stones.len() == 1 && last_move.unwrap() == group.stones[0] { //if 2 groups died we can safely unwrap last_move
-                    illegal = true;
-                    false
-                } else {
-                    true
-                }
-            } else {
-                true
-            }
-        }).collect::<Vec<Group>>();
-
-        if illegal {
-            Err(())
-        } else {
-            Ok(dead_groups)
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Move {
-    pub player: Player,
-    pub index: usize,
-    pub groups: Vec<Group>,
-}
-
-impl From<(Player, usize, Vec<Group>)> for Move {
-    fn from(m: (Player, usize, Vec<Group>)) -> Self {
-        Move {
-            player: m.0,
-            index: m.1,
-            groups: m.2,
-        }
-    }
-}
-
-impl Into<(Player, usize, Vec<Group>)> for Move {
-    fn into(self) -> (Player, usize, Vec<Group>) {
-        (self.player, self.index, self.groups)
-    }
-}
-
-impl Goban {
-    pub fn previous_state(&mut self) -> bool {
-        if let Some((previous_move, (player, played_move, dead_stones))) = self.history.pop() {
-            self.stones[played_move] = Stone::default();
-            for group in dead_stones {
-                for p in &group.stones {
-                    let i = Goban::coord_to_idx(*p);
-                    self.stones[i] = match player {
-                        Player::Black => Stone::white(),
-                        Player::White => Stone::black(),
-                    }
-                }
-            }
-            self.turn.next();
-            self.last_move = if let Some(idx) = previous_move {
-                Some(Goban::idx_to_coord(idx))
-            } else {
-                None
-            };
-            self.current_move_number -= 1;
-
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn next_state(&mut self) -> bool {
-        if let Some((player, played_move, dead_stones)) = self.history.next() {
-            self.stones[played_move] = match player {
-                Player::Black => Stone::black(),
-                Player::White => Stone::white(),
-            };
-            for group in dead_stones {
-                for p in &group.stones {
-                    let i = Goban::coord_to_idx(*p);
-                    self.stones[i] = Stone::default();
-                }
-            }
-            self.turn.next();
-            self.last_move = Some(Goban::idx_to_coord(played_move));
-            self.current_move_number += 1;
-
-            true
-        } else {
-            false
-        }
-    }
-}
@@ -1,5 +1,3 @@
-#[macro_use] extern crate lazy_static;
-
 use std::io::Write;
 use log::info;
 use iced::{executor, Application, Command, Element, Settings, window};
@@ -13,53 +11,23 @@ use engine::Engine;
 mod goban;
 use goban::Goban;
 
-mod history;
+mod game_tree;
+use game_tree::GameTree;
+
 mod engine_commands;
 
+use ainalyzer_core::{Player, rules, scoring};
+use ainalyzer_core::collection::{Collection, GameSummary};
+use ainalyzer_core::board::{Point, Stone};
+use ainalyzer_core::history::{History, NodeIndex};
+use ainalyzer_core::undo::Edit;
+use ainalyzer_core::influence::Influence;
+
 mod winrate_plot;
 use winrate_plot::WinratePlot;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Player {
-    Black,
-    White,
-}
-
-impl Player {
-    fn next(&mut self) {
-        match self {
-            Self::Black => *self = Self::White,
-            Self::White => *self = Self::Black,
-        }
-    }
-}
-
-impl std::fmt::Display for Player {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", match self {
-            Self::Black => "B",
-            Self::White => "W",
-        })
-    }
-}
-
-impl Into<sgf_parser::Color> for Player {
-    fn into(self) -> sgf_parser::Color {
-        match self {
-            Self::Black => sgf_parser::Color::Black,
-            Self::White => sgf_parser::Color::White,
-        }
-    }
-}
-
-impl Into<iced::Color> for Player {
-    fn into(self) -> iced::Color {
-        match self {
-            Self::Black => iced::Color::BLACK,
-            Self::White => iced::Color::WHITE,
-        }
-    }
-}
+mod recovery;
+use recovery::Recovery;
 
 // State machine
 rust_fsm::state_machine! {
@@ -72,14 +40,52 @@ rust_fsm::state_machine! {
 
 #[derive(Debug, Clone, Copy)]
 pub enum GobanEvent {
-    Play(goban::Point, goban::Stone),
+    Play(Point, Stone),
+    Pass,
+    ToggleEdit,
+    Edit(Point, Option<Player>),
+    SetTurn(Player),
+    ToggleMarkDead,
+    ToggleDead(Point),
+    MarkDeadFromOwnership,
+    ToggleTactics,
+    ToggleInfluence,
+    Rotate,
+    Flip,
+    ToggleWhiteSide,
+    SetMoveNumbers(goban::MoveNumbers),
+    SetMarkupTool(goban::MarkupTool),
+    // from and to, the same point for shapes and labels
+    Mark(goban::MarkupTool, Point, Point),
+    // jumps to any node of the game tree
+    GoTo(NodeIndex<u32>),
+    // edits only, moving through the game isn't undone
+    Undo,
+    Redo,
+    GoToStart,
+    GoToEnd,
+    // on the current line, the last move when it is shorter
+    GoToMove(u32),
+    NextFork,
+    PreviousFork,
+    DeleteVariation,
+    PromoteVariation,
+    // true moves it before its previous sibling
+    ShiftVariation(bool),
     PreviousState,
     NextState,
 }
 
 #[derive(Debug, Clone)]
 pub enum EngineCommand {
-    EnginePlay(Player, goban::Point, Option<(u64, f32)>),
+    EnginePlay(Player, Option<Point>, Option<(u64, f32)>),
+    EngineSetPosition(Vec<(Player, Point)>),
+    // black stones on an empty board, white moves next
+    EngineHandicap(Vec<Point>),
+    // clears the engine's board then sends every command in order
+    EngineReplay(Vec<EngineCommand>),
+    // sends every command in order from where the engine stands
+    EngineSync(Vec<EngineCommand>),
     EngineUndo,
 }
 
@@ -94,16 +100,47 @@ pub enum Message {
     RefreshAnalyze,
     StopAnalyze,
     OpenFile(std::path::PathBuf),
+    OpenGame(GameSummary),
+    NewGame(u32),
+    SetRules(rules::Rules),
+    SetKoRule(rules::KoRule),
+    Score,
+    WriteResult,
+    ToggleCommentEdit,
+    SetComment(String),
+    CommentNewLine,
+    ToggleMoveInput,
+    SetMoveInput(String),
+    GoToMoveInput,
+    Autosave(std::time::Instant),
+    RestoreSession,
+    DiscardSession,
     DialogCancel,
 }
 
+// the comment input's widget id, to focus it
+const COMMENT_INPUT: &str = "comment";
+const MOVE_INPUT: &str = "move";
+
 struct Ainalyzer {
     engine: Engine,
     engine_state: rust_fsm::StateMachine<EngineState>,
     goban: Goban,
     winrate_plot: WinratePlot,
     opened_file: Option<std::path::PathBuf>,
+    // every game of the opened file, the reviewed one is in the goban
+    collection: Collection,
     file_updated: bool,
+    // shortcuts are off while typing a comment
+    editing_comment: bool,
+    // the node whose comment this typing session already logged for undo
+    comment_undo: Option<NodeIndex<u32>>,
+    // shortcuts are off while typing a move number too
+    editing_move: bool,
+    // the move number typed to jump to
+    move_input: String,
+    // an unsaved review left by the last run, waiting to be restored or discarded
+    recovery: Option<Recovery>,
 }
 
 impl Application for Ainalyzer {
@@ -113,13 +150,20 @@ impl Application for Ainalyzer {
     type Theme = iced::theme::Theme;
 
     fn new(_flags: ()) -> (Self, Command<Self::Message>) {
+        let goban = Goban::default();
         (Self {
             engine: Engine::new(),
             engine_state: rust_fsm::StateMachine::new(),
-            goban: Goban::default(),
+            collection: Collection::from(&goban.board.history),
+            goban,
             winrate_plot: WinratePlot::new(),
             opened_file: None,
             file_updated: true,
+            editing_comment: false,
+            comment_undo: None,
+            editing_move: false,
+            move_input: String::new(),
+            recovery: Recovery::load(),
         }, Command::none())
     }
 
@@ -142,6 +186,11 @@ impl Application for Ainalyzer {
         match message {
             Message::Event(event) => {
                 match event {
+                    Event::Keyboard(iced_native::keyboard::Event::KeyReleased{ key_code, .. }) if self.editing_comment || self.editing_move => {
+                        if key_code == KeyCode::Escape {
+                            return self.update(if self.editing_comment { Message::ToggleCommentEdit } else { Message::ToggleMoveInput })
+                        }
+                    },
                     Event::Keyboard(iced_native::keyboard::Event::KeyReleased{ key_code, modifiers}) => {
                         if modifiers.is_empty() {
                             match key_code {
@@ -149,6 +198,60 @@ impl Application for Ainalyzer {
                                 KeyCode::W => {
                                     self.engine.ownership = !self.engine.ownership;
                                 },
+                                KeyCode::P => {
+                                    let _ = self.update(Message::Goban(GobanEvent::Pass));
+                                },
+                                KeyCode::E => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ToggleEdit));
+                                },
+                                KeyCode::D => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ToggleMarkDead));
+                                },
+                                KeyCode::T => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ToggleTactics));
+                                },
+                                KeyCode::I => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ToggleInfluence));
+                                },
+                                KeyCode::R => {
+                                    let _ = self.update(Message::Goban(GobanEvent::Rotate));
+                                },
+                                KeyCode::F => {
+                                    let _ = self.update(Message::Goban(GobanEvent::Flip));
+                                },
+                                KeyCode::V => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ToggleWhiteSide));
+                                },
+                                KeyCode::M => {
+                                    let _ = self.update(Message::Goban(GobanEvent::SetMoveNumbers(self.goban.move_numbers.next())));
+                                },
+                                KeyCode::Home => {
+                                    let _ = self.update(Message::Goban(GobanEvent::GoToStart));
+                                },
+                                KeyCode::End => {
+                                    let _ = self.update(Message::Goban(GobanEvent::GoToEnd));
+                                },
+                                KeyCode::G => return self.update(Message::ToggleMoveInput),
+                                KeyCode::LBracket => {
+                                    let _ = self.update(Message::Goban(GobanEvent::PreviousFork));
+                                },
+                                KeyCode::RBracket => {
+                                    let _ = self.update(Message::Goban(GobanEvent::NextFork));
+                                },
+                                KeyCode::Delete => {
+                                    let _ = self.update(Message::Goban(GobanEvent::DeleteVariation));
+                                },
+                                KeyCode::PageUp => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ShiftVariation(true)));
+                                },
+                                KeyCode::PageDown => {
+                                    let _ = self.update(Message::Goban(GobanEvent::ShiftVariation(false)));
+                                },
+                                KeyCode::Escape => {
+                                    if let goban::GobanMode::Markup(tool) = self.goban.mode {
+                                        let _ = self.update(Message::Goban(GobanEvent::SetMarkupTool(tool)));
+                                    }
+                                },
                                 KeyCode::Space => {
                                     match self.engine_state.state() {
                                         EngineStateState::Idle => {
@@ -174,23 +277,34 @@ impl Application for Ainalyzer {
                                         })
                                 },
                                 KeyCode::N => {
-                                    self.goban = Goban::default();
-                                    self.opened_file = None;
-                                    self.file_updated = true;
+                                    return self.update(Message::NewGame(self.goban.board.board_size));
                                 }
+                                KeyCode::M => {
+                                    let _ = self.update(Message::Goban(GobanEvent::PromoteVariation));
+                                },
+                                KeyCode::Z if modifiers.shift() => {
+                                    let _ = self.update(Message::Goban(GobanEvent::Redo));
+                                },
+                                KeyCode::Z => {
+                                    let _ = self.update(Message::Goban(GobanEvent::Undo));
+                                },
                                 KeyCode::S => {
                                     match &self.opened_file {
                                         Some(path) => {
                                             let file = std::fs::OpenOptions::new()
                                                 .write(true)
+                                                .truncate(true)
                                                 .create(true)
                                                 .open(path.clone())
                                                 .expect("couldn't create/open file");
 
                                             let mut bufw = std::io::BufWriter::new(file);
-                                            let sgf: String = self.goban.history.into_game_tree().into();
+                                            self.collection.store(&self.goban.board.history);
+                                            let sgf = self.collection.to_sgf();
                                             bufw.write_all(sgf.as_bytes()).expect("couldn't write to file");
                                             self.file_updated = true;
+                                            self.goban.undo.mark_saved();
+                                            Recovery::remove();
                                         },
                                         None => {
                                             let path = FileDialog::new().show_save_single_file().expect("Save dialog failed");
@@ -199,14 +313,18 @@ impl Application for Ainalyzer {
                                                     self.opened_file = Some(p.clone());
                                                     let file = std::fs::OpenOptions::new()
                                                         .write(true)
+                                                        .truncate(true)
                                                         .create(true)
                                                         .open(p)
                                                         .expect("couldn't create/open file");
 
                                                     let mut bufw = std::io::BufWriter::new(file);
-                                                    let sgf: String = self.goban.history.into_game_tree().into();
+                                                    self.collection.store(&self.goban.board.history);
+                                                    let sgf = self.collection.to_sgf();
                                                     bufw.write_all(sgf.as_bytes()).expect("couldn't write to file");
                                                     self.file_updated = true;
+                                                    self.goban.undo.mark_saved();
+                                                    Recovery::remove();
                                                 },
                                                 None => (),
                                             }
@@ -224,7 +342,7 @@ impl Application for Ainalyzer {
                 match self.engine_state.state() {
                     EngineStateState::Idle => {
                         let _ = self.engine_state.consume(&EngineStateInput::StartAnalyze);
-                        self.engine.start_analyze();
+                        self.engine.start_analyze(self.goban.board.turn);
                     },
                     EngineStateState::Analyzing => (),
                 }
@@ -232,7 +350,7 @@ impl Application for Ainalyzer {
             Message::RefreshAnalyze => {
                 match self.engine_state.state() {
                     EngineStateState::Analyzing => {
-                        self.engine.start_analyze();
+                        self.engine.start_analyze(self.goban.board.turn);
                     },
                     EngineStateState::Idle => (),
                 }
@@ -251,12 +369,12 @@ impl Application for Ainalyzer {
                     Some(info) => {
                         self.goban.analyze_info = Some(goban::AnalyzeInfo(info));
                         let winrate = self.goban.analyze_info.as_ref().unwrap().max_winrate();
-                        let winrate = if self.goban.turn == Player::Black {
+                        let winrate = if self.goban.board.turn == Player::Black {
                             100.0 - winrate
                         } else {
                             winrate
                         };
-                        self.winrate_plot.update_plot((self.goban.current_move_number as u64, winrate));
+                        self.winrate_plot.update_plot((self.goban.board.current_move_number as u64, winrate));
                     },
                     None => (),
                 }
@@ -265,10 +383,160 @@ impl Application for Ainalyzer {
             Message::EngineError => (),
             Message::OpenFile(path) => {
                 let sgf = std::fs::read_to_string(path.clone()).expect("failed to load sgf");
-                let game = sgf_parser::parse(sgf.as_str()).expect("failed to parse sgf");
-                self.opened_file = Some(path);
-                self.goban = Goban::default();
-                self.goban.history = history::History::from(game);
+                // a file we can't review leaves the current one open
+                match Collection::parse(sgf.as_str()) {
+                    Ok(collection) => match collection.history() {
+                        Ok(history) => {
+                            self.collection = collection;
+                            self.opened_file = Some(path);
+                            self.open_game(history);
+                            self.file_updated = true;
+                        },
+                        Err(e) => log::error!("couldn't open {}: {}", path.display(), e),
+                    },
+                    Err(e) => log::error!("couldn't parse {}: {}", path.display(), e),
+                }
+            },
+            Message::OpenGame(game) => {
+                // the edits stay with the collection until it is saved
+                self.collection.store(&self.goban.board.history);
+                let previous = self.collection.current;
+                self.collection.current = game.index;
+                match self.collection.history() {
+                    Ok(history) => {
+                        self.open_game(history);
+                        if !self.file_updated {
+                            self.goban.undo.mark_unsaved();
+                        }
+                    },
+                    Err(e) => {
+                        log::error!("couldn't open game {}: {}", game, e);
+                        self.collection.current = previous;
+                    },
+                }
+            },
+            // only shown, the game isn't touched until the result is written
+            Message::Score => {
+                self.goban.score = Some(scoring::Score::new(&self.goban.board, &self.goban.dead_stones));
+            },
+            Message::WriteResult => {
+                let score = scoring::Score::new(&self.goban.board, &self.goban.dead_stones);
+                let history = &mut self.goban.board.history;
+                let node = history.current_index;
+                let before = (history.result(), history.moves[node].territory.clone());
+                history.set_result(Some(score.result()));
+                history.set_territory(score.territory.clone());
+                let after = (history.result(), score.territory.clone());
+                self.goban.undo.record(Edit::Score { node, before, after });
+                self.goban.score = Some(score);
+                self.file_updated = false;
+            },
+            Message::ToggleCommentEdit => {
+                self.editing_comment = !self.editing_comment;
+                self.editing_move = false;
+                self.comment_undo = None;
+                if self.editing_comment {
+                    return iced::widget::text_input::focus(iced::widget::text_input::Id::new(COMMENT_INPUT))
+                }
+            },
+            Message::SetComment(comment) => {
+                let node = self.goban.board.history.current_index;
+                let before = self.goban.board.history.comment().to_string();
+                self.goban.board.history.set_comment(comment.clone());
+                // a whole typing session on a node is undone at once
+                let edit = Edit::Comment { node, before, after: comment };
+                if self.comment_undo == Some(node) {
+                    self.goban.undo.amend(edit);
+                } else {
+                    self.goban.undo.record(edit);
+                }
+                self.comment_undo = Some(node);
+                self.file_updated = false;
+            },
+            Message::CommentNewLine => {
+                let comment = format!("{}\n", self.goban.board.history.comment());
+                return self.update(Message::SetComment(comment))
+            },
+            Message::ToggleMoveInput => {
+                if self.editing_comment {
+                    let _ = self.update(Message::ToggleCommentEdit);
+                }
+                self.editing_move = !self.editing_move;
+                self.move_input.clear();
+                if self.editing_move {
+                    return iced::widget::text_input::focus(iced::widget::text_input::Id::new(MOVE_INPUT))
+                }
+            },
+            Message::SetMoveInput(input) => {
+                self.move_input = input.chars().filter(|c| c.is_ascii_digit()).collect();
+            },
+            Message::GoToMoveInput => {
+                if let Ok(n) = self.move_input.parse() {
+                    let _ = self.update(Message::Goban(GobanEvent::GoToMove(n)));
+                }
+                return self.update(Message::ToggleMoveInput)
+            },
+            Message::Autosave(_) => {
+                if self.file_updated {
+                    Recovery::remove();
+                } else {
+                    self.collection.store(&self.goban.board.history);
+                    Recovery {
+                        opened_file: self.opened_file.clone(),
+                        current_game: self.collection.current,
+                        sgf: self.collection.to_sgf(),
+                    }.save();
+                }
+            },
+            Message::RestoreSession => {
+                if let Some(recovery) = self.recovery.take() {
+                    match Collection::parse(&recovery.sgf) {
+                        Ok(mut collection) => {
+                            collection.current = recovery.current_game.min(collection.games.len() - 1);
+                            match collection.history() {
+                                Ok(history) => {
+                                    self.collection = collection;
+                                    self.opened_file = recovery.opened_file;
+                                    self.open_game(history);
+                                    self.goban.undo.mark_unsaved();
+                                    self.file_updated = false;
+                                    // the restored review is autosaved like any other from now on
+                                    Recovery::remove_previous();
+                                },
+                                Err(e) => log::error!("couldn't read the recovered review: {}", e),
+                            }
+                        },
+                        Err(e) => log::error!("couldn't read the recovered review: {}", e),
+                    }
+                }
+            },
+            Message::DiscardSession => {
+                self.recovery = None;
+                Recovery::remove_previous();
+            },
+            Message::SetRules(rules) => {
+                let history = &mut self.goban.board.history;
+                let before = (history.rules, history.rule_set().cloned());
+                history.set_rules(rules);
+                let after = (history.rules, history.rule_set().cloned());
+                self.goban.undo.record(Edit::Rules { before, after });
+                self.engine.set_rules(&rules);
+                self.file_updated = false;
+            },
+            // a ko rule no preset plays with is saved as custom rules
+            Message::SetKoRule(ko_rule) => {
+                let rules = self.goban.board.history.rules.with_ko(ko_rule);
+                let _ = self.update(Message::SetRules(rules));
+            },
+            Message::NewGame(board_size) => {
+                let (orientation, move_numbers) = (self.goban.orientation, self.goban.move_numbers);
+                self.goban = Goban::new(board_size);
+                self.goban.orientation = orientation;
+                self.goban.move_numbers = move_numbers;
+                self.engine.set_board_size(board_size);
+                self.engine.set_rules(&self.goban.board.history.rules);
+                self.collection = Collection::from(&self.goban.board.history);
+                self.opened_file = None;
                 self.file_updated = true;
             },
             Message::EngineCommand(c) => {
@@ -278,33 +546,39 @@ impl Application for Ainalyzer {
                             Ok(answer) => match answer {
                                 libgtp::Answer::Failure(f) => {
                                     log::error!("{:?}", f);
-                                    self.goban.previous_state();
+                                    self.goban.board.previous_state();
                                 },
                                 _ => (),
                             },
                             Err(e) => {
                                 log::error!("{}", e);
-                                self.goban.previous_state();
+                                self.goban.board.previous_state();
                             }
                         }
                     },
+                    EngineCommand::EngineSetPosition(stones) => self.engine.set_position(&stones),
+                    EngineCommand::EngineHandicap(stones) => self.engine.set_free_handicap(&stones),
+                    EngineCommand::EngineReplay(commands) => {
+                        self.engine.clear_board();
+                        self.sync_engine(commands);
+                    },
+                    EngineCommand::EngineSync(commands) => self.sync_engine(commands),
                     EngineCommand::EngineUndo => self.engine.undo(),
                 }
             },
             Message::Goban(e) => {
-                match &e {
-                    GobanEvent::Play(_, _) => {
-                        self.file_updated = false;
-                    },
-                    _ => (),
-                }
-
                 match self.goban.update(message) {
                     Some(c) => {
                         let _ = self.update(c);
                     },
                     None => (),
                 }
+                self.goban.read_tactics();
+                // every edit is logged, undoing them all gets back to the saved file
+                self.file_updated = self.goban.undo.is_saved();
+                if let GobanEvent::Undo | GobanEvent::Redo = e {
+                    self.engine.set_rules(&self.goban.board.history.rules);
+                }
             },
             _ => (),
         };
@@ -312,33 +586,153 @@ impl Application for Ainalyzer {
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
+        let autosave = iced::time::every(recovery::AUTOSAVE_INTERVAL).map(Message::Autosave);
         match self.engine_state.state() {
             EngineStateState::Analyzing => {
                 let timer = iced::time::every(engine_commands::TIMER_INTERVAL)
                     .map(Message::EngineTick);
                 let runtime = iced_native::subscription::events().map(Message::Event);
-                iced::Subscription::batch(vec![timer, runtime])
+                iced::Subscription::batch(vec![timer, runtime, autosave])
             },
-            _ => iced_native::subscription::events().map(Message::Event),
+            _ => iced::Subscription::batch(vec![iced_native::subscription::events().map(Message::Event), autosave]),
         }
         
     }
 
     fn view(&self) -> Element<Self::Message> {
-        let left_column = column!(self.winrate_plot.view().explain(iced::Color::from_rgb(1.0, 0.0, 0.0)),
+        let new_game = row!(
+            iced::widget::button("9x9").on_press(Message::NewGame(9)),
+            iced::widget::button("13x13").on_press(Message::NewGame(13)),
+            iced::widget::button("19x19").on_press(Message::NewGame(19)))
+            .spacing(20);
+
+        // only files holding several games need a picker
+        let games: Element<Message> = if self.collection.games.len() > 1 {
+            let summaries = self.collection.summaries();
+            let current = summaries.get(self.collection.current).cloned();
+            iced::widget::pick_list(summaries, current, Message::OpenGame).into()
+        } else {
+            iced::widget::vertical_space(iced::Length::Shrink).into()
+        };
+
+        let recovery: Element<Message> = match &self.recovery {
+            Some(recovery) => row!(iced::widget::text(format!("Unsaved review found{}", recovery.opened_file.as_ref()
+                    .map(|p| format!(" for {}", p.display()))
+                    .unwrap_or_default())),
+                iced::widget::button("Restore").on_press(Message::RestoreSession),
+                iced::widget::button("Discard").on_press(Message::DiscardSession))
+                .spacing(20)
+                .into(),
+            None => iced::widget::vertical_space(iced::Length::Shrink).into(),
+        };
+
+        // the field only exists while typing, like the comment's
+        let move_input: Element<Message> = if self.editing_move {
+            iced::widget::text_input("Move", &self.move_input, Message::SetMoveInput)
+                .id(iced::widget::text_input::Id::new(MOVE_INPUT))
+                .on_submit(Message::GoToMoveInput)
+                .width(iced::Length::Units(60))
+                .into()
+        } else {
+            iced::widget::button("Move").on_press(Message::ToggleMoveInput).into()
+        };
+        // the turn is only kept by the root or a setup node at the end of its line, the buttons are off elsewhere
+        let turn_button = |label: &'static str, player: Player| {
+            let button = iced::widget::button(label);
+            if self.goban.board.can_set_turn() && self.goban.board.turn != player {
+                button.on_press(Message::Goban(GobanEvent::SetTurn(player)))
+            } else {
+                button
+            }
+        };
+        // custom rules are listed while they are the game's
+        let mut rule_options = rules::Rules::ALL.to_vec();
+        if !rule_options.contains(&self.goban.board.history.rules) {
+            rule_options.push(self.goban.board.history.rules);
+        }
+        let left_column = column!(recovery,
+            self.winrate_plot.view().explain(iced::Color::from_rgb(1.0, 0.0, 0.0)),
             iced::widget::vertical_space(iced::Length::FillPortion(1)),
+            new_game,
+            games,
+            row!(iced::widget::pick_list(rule_options, Some(self.goban.board.history.rules), Message::SetRules),
+                iced::widget::pick_list(&rules::KoRule::ALL[..], Some(self.goban.board.history.rules.ko), Message::SetKoRule))
+                .spacing(20),
+            row!(iced::widget::button("Score").on_press(Message::Score),
+                iced::widget::button("Write result").on_press(Message::WriteResult),
+                iced::widget::button("Mark dead stones").on_press(Message::Goban(GobanEvent::ToggleMarkDead)),
+                iced::widget::button("Mark from engine").on_press(Message::Goban(GobanEvent::MarkDeadFromOwnership)))
+                .spacing(20),
+            row!(iced::widget::button("Edit board").on_press(Message::Goban(GobanEvent::ToggleEdit)),
+                turn_button("Black to play", Player::Black),
+                turn_button("White to play", Player::White))
+                .spacing(20),
+            row!(iced::widget::button("|<").on_press(Message::Goban(GobanEvent::GoToStart)),
+                iced::widget::button("<<").on_press(Message::Goban(GobanEvent::PreviousFork)),
+                move_input,
+                iced::widget::button(">>").on_press(Message::Goban(GobanEvent::NextFork)),
+                iced::widget::button(">|").on_press(Message::Goban(GobanEvent::GoToEnd)))
+                .spacing(20),
+            row!(iced::widget::button("Delete variation").on_press(Message::Goban(GobanEvent::DeleteVariation)),
+                iced::widget::button("Main line").on_press(Message::Goban(GobanEvent::PromoteVariation)),
+                iced::widget::button("Up").on_press(Message::Goban(GobanEvent::ShiftVariation(true))),
+                iced::widget::button("Down").on_press(Message::Goban(GobanEvent::ShiftVariation(false))))
+                .spacing(20),
+            row!(iced::widget::button("Ataris and ladders").on_press(Message::Goban(GobanEvent::ToggleTactics)),
+                iced::widget::button("Influence").on_press(Message::Goban(GobanEvent::ToggleInfluence)))
+                .spacing(20),
+            row!(iced::widget::button("Rotate").on_press(Message::Goban(GobanEvent::Rotate)),
+                iced::widget::button("Flip").on_press(Message::Goban(GobanEvent::Flip)),
+                iced::widget::button("White's side").on_press(Message::Goban(GobanEvent::ToggleWhiteSide)))
+                .spacing(20),
+            row!(iced::widget::pick_list(&goban::MoveNumbers::ALL[..], Some(self.goban.move_numbers), |m| Message::Goban(GobanEvent::SetMoveNumbers(m))),
+                iced::widget::pick_list(&goban::MarkupTool::ALL[..], match self.goban.mode {
+                    goban::GobanMode::Markup(tool) => Some(tool),
+                    _ => None,
+                }, |t| Message::Goban(GobanEvent::SetMarkupTool(t))).placeholder("Markup"))
+                .spacing(20),
+            iced::widget::text(format!("Captures  B: {}  W: {}", self.goban.board.black_prisoners, self.goban.board.white_prisoners)),
+            iced::widget::text(self.goban.score.as_ref().map(|s| s.to_string()).unwrap_or_default()),
+            iced::widget::text(if self.goban.show_influence {
+                format!("Estimate: {}", Influence::new(&self.goban.board).score(&self.goban.board, &self.goban.dead_stones))
+            } else {
+                String::new()
+            }),
             self.engine.view(),
             iced::widget::vertical_space(iced::Length::FillPortion(5)))
                 .width(iced::Length::FillPortion(1))
                 .height(iced::Length::FillPortion(6));
 
+        // the comment of the current node, follows the navigation
+        let comment_input: Element<Message> = if self.editing_comment {
+            iced::widget::text_input("Comment", self.goban.board.history.comment(), Message::SetComment)
+                .id(iced::widget::text_input::Id::new(COMMENT_INPUT))
+                .on_submit(Message::CommentNewLine)
+                .into()
+        } else {
+            iced::widget::vertical_space(iced::Length::Shrink).into()
+        };
+        let comment_panel = column!(GameTree::new(&self.goban.board.history).view(),
+            iced::widget::text("Comment"),
+            iced::widget::scrollable(iced::widget::text(self.goban.board.history.comment()))
+                .height(iced::Length::FillPortion(5)),
+            comment_input,
+            iced::widget::button(if self.editing_comment { "Done" } else { "Edit comment" }).on_press(Message::ToggleCommentEdit),
+            iced::widget::vertical_space(iced::Length::FillPortion(1)))
+                .spacing(10)
+                .padding(30)
+                .width(iced::Length::FillPortion(1))
+                .height(iced::Length::FillPortion(6));
+
         row!(
             left_column,
-            column!(self.goban.view())
+            column!(self.goban.view(),
+                iced::widget::button("Pass").on_press(Message::Goban(GobanEvent::Pass)))
                 .spacing(0)
                 .padding(30)
                 .width(iced::Length::FillPortion(2))
-                .align_items(iced::Alignment::Center)
+                .align_items(iced::Alignment::Center),
+            comment_panel
         )
         .spacing(0)
         .padding(0)
@@ -349,6 +743,36 @@ impl Application for Ainalyzer {
     }
 }
 
+impl Ainalyzer {
+    // the board already stands where these commands lead, a failure is only logged
+    fn sync_engine(&mut self, commands: Vec<EngineCommand>) {
+        for c in commands {
+            match c {
+                EngineCommand::EnginePlay(t, p, _) => match self.engine.play(t, p, None) {
+                    Ok(libgtp::Answer::Failure(f)) => log::error!("{:?}", f),
+                    Err(e) => log::error!("{}", e),
+                    _ => (),
+                },
+                EngineCommand::EngineSetPosition(stones) => self.engine.set_position(&stones),
+                EngineCommand::EngineHandicap(stones) => self.engine.set_free_handicap(&stones),
+                EngineCommand::EngineUndo => self.engine.undo(),
+                _ => (),
+            }
+        }
+    }
+
+    // shows the collection's current game and brings the engine to its start
+    fn open_game(&mut self, history: History) {
+        let (orientation, move_numbers) = (self.goban.orientation, self.goban.move_numbers);
+        self.goban = Goban::from(history);
+        self.goban.orientation = orientation;
+        self.goban.move_numbers = move_numbers;
+        self.engine.set_board_size(self.goban.board.board_size);
+        self.engine.set_rules(&self.goban.board.history.rules);
+        let _ = self.update(self.goban.engine_replay());
+    }
+}
+
 fn main() {
     scrub_log::init().unwrap();
     info!("Starting the app");
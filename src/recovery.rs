@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+// next to the engine, in the working directory
+const RECOVERY_FILE: &str = "./ainalyzer.recovery";
+// the last run's review is moved aside while the user decides, autosave goes on meanwhile
+const PREVIOUS_RECOVERY_FILE: &str = "./ainalyzer.recovery.previous";
+pub const AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// An unsaved review written now and then, a crash only loses the last moments.
+// The file only exists while there are unsaved edits: the opened path on the
+// first line, the reviewed game on the second and the whole collection after them
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    pub opened_file: Option<PathBuf>,
+    pub current_game: usize,
+    pub sgf: String,
+}
+
+impl Recovery {
+    // a review left aside by a run that ended before any choice is offered again
+    pub fn load() -> Option<Self> {
+        if let Err(e) = std::fs::rename(RECOVERY_FILE, PREVIOUS_RECOVERY_FILE) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::error!("couldn't move the recovery file aside: {}", e);
+            }
+        }
+        let content = std::fs::read_to_string(PREVIOUS_RECOVERY_FILE).ok()?;
+        let mut lines = content.splitn(3, '\n');
+        let opened_file = match lines.next()? {
+            "" => None,
+            path => Some(PathBuf::from(path)),
+        };
+        let current_game = lines.next()?.parse().ok()?;
+        let sgf = lines.next()?.to_string();
+        Some(Self { opened_file, current_game, sgf })
+    }
+
+    pub fn save(&self) {
+        let path = self.opened_file.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+        if let Err(e) = std::fs::write(RECOVERY_FILE, format!("{}\n{}\n{}", path, self.current_game, self.sgf)) {
+            log::error!("couldn't write the recovery file: {}", e);
+        }
+    }
+
+    // nothing left to recover once the review is saved
+    pub fn remove() {
+        remove_file(RECOVERY_FILE);
+    }
+
+    // the last run's review was restored or discarded
+    pub fn remove_previous() {
+        remove_file(PREVIOUS_RECOVERY_FILE);
+    }
+}
+
+fn remove_file(path: &str) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::error!("couldn't remove the recovery file: {}", e);
+        }
+    }
+}